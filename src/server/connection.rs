@@ -19,6 +19,10 @@ enum StreamReadState {
     Message,
     /// 3
     PlayerPos,
+    /// 4
+    Dimensions,
+    /// 5
+    Weather,
 }
 
 impl StreamReadState {
@@ -29,6 +33,8 @@ impl StreamReadState {
             StreamReadState::PreMessage => Some(1),
             StreamReadState::Message => None,
             StreamReadState::PlayerPos => Some(12),
+            StreamReadState::Dimensions => Some(6),
+            StreamReadState::Weather => Some(2),
         }
     }
 }
@@ -41,6 +47,8 @@ impl From<u8> for StreamReadState {
             1 => PreMessage,
             2 => Message,
             3 => PlayerPos,
+            4 => Dimensions,
+            5 => Weather,
             _ => unreachable!(),
         }
     }
@@ -98,6 +106,16 @@ impl ClientConnection {
                 PreMessage => dynamic_size = data[0] as usize,
                 Message => todo!(), // add to chat log or something
                 PlayerPos => todo!(), // update recorded position
+                Dimensions => {
+                    // only the server sends these
+                    log::warn!("ignoring a client resizing the world");
+                    self.state = Unset;
+                }
+                Weather => {
+                    // only the server sends these
+                    log::warn!("ignoring a client changing the weather");
+                    self.state = Unset;
+                }
             }
 
             amt_read += n;
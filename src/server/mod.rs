@@ -5,13 +5,18 @@ use std::{
     thread::{self, JoinHandle},
 };
 
-use crate::world::{position, update::WorldUpdate, World};
+use crate::world::{
+    position, update::WorldUpdate, GameEvent, GenerationConfig, Lighting, WeatherState, World,
+};
 
 use self::network::ClientManagerHandle;
 
 mod connection;
 mod network;
 
+/// how many ticks go by between packing the states world back down
+const COMPACT_INTERVAL: u64 = 256;
+
 pub struct ServerHandle {
     send: Sender<()>,
     jh: Option<JoinHandle<()>>,
@@ -42,26 +47,85 @@ struct Server {
     /// for all internally tracked data. Doesn't need to be sent
     /// out so it's stored separately for cache efficiency
     states: World,
+    /// light levels, kept up to date with every block that changes
+    lighting: Lighting,
+    /// the weather as last sent out, so it's only sent again when it changes
+    weather: WeatherState,
     client_handler: ClientManagerHandle,
 }
 
 impl Server {
     fn new(addr: SocketAddr) -> io::Result<Server> {
-        let client_handler = ClientManagerHandle::start(addr)?;
+        let blocks = World::generate(&GenerationConfig::default())
+            .expect("the default generation config is valid");
+        let client_handler = ClientManagerHandle::start(addr, blocks.dimensions())?;
+        // almost every block has no state, so this is mostly uniform chunks
+        let mut states = World::with_dimensions(blocks.dimensions(), 0);
+        states.compact();
+        let lighting = Lighting::new(&blocks);
 
         Ok(Server {
-            blocks: World::generate(),
-            states: World::empty(),
+            blocks,
+            states,
+            lighting,
+            weather: WeatherState::default(),
             client_handler,
         })
     }
 
     fn process_update(&mut self, update: WorldUpdate, updates_to_send: &mut Vec<WorldUpdate>) {
-        todo!()
+        match update {
+            WorldUpdate::Block(update) => {
+                let dims = self.blocks.dimensions();
+                let Some(pos) =
+                    position::checked_from_ccb(dims, update.chunk, update.column, update.block)
+                else {
+                    log::warn!("ignoring block update outside of the world");
+                    return;
+                };
+                if !self.blocks.process_update(update) {
+                    log::warn!("ignoring block update with invalid block data");
+                    return;
+                }
+                // placed blocks start out fresh, e.g. placed water is a source
+                self.states.get_block_mut(pos).data = 0;
+                self.lighting.process_update(&self.blocks, update);
+                updates_to_send.push(WorldUpdate::Block(update));
+            }
+            WorldUpdate::Dimensions(_) => log::warn!("ignoring a client resizing the world"),
+            WorldUpdate::Weather(_) => log::warn!("ignoring a client changing the weather"),
+        }
     }
 
     fn update_world(&mut self, updates_to_send: &mut Vec<WorldUpdate>) {
-        todo!()
+        let report = self.blocks.simulate(&mut self.states);
+        log::debug!(
+            "tick {} simulated {} chunks",
+            self.blocks.ticks(),
+            report.chunks
+        );
+        for &update in &report.updates {
+            self.lighting.process_update(&self.blocks, update);
+        }
+        updates_to_send.extend(report.updates.into_iter().map(WorldUpdate::Block));
+        for event in report.events {
+            match event {
+                GameEvent::ShrineReached(pos) => {
+                    let (x, y, z) = position::to_xyz(self.blocks.dimensions(), pos);
+                    log::warn!("the ill reached the shrine at {x}, {y}, {z}");
+                }
+            }
+        }
+
+        let weather = self.blocks.weather();
+        if weather != self.weather {
+            self.weather = weather;
+            updates_to_send.push(WorldUpdate::Weather(weather));
+        }
+
+        if self.blocks.ticks().is_multiple_of(COMPACT_INTERVAL) {
+            self.states.compact();
+        }
     }
 
     fn run(mut self, recv: Receiver<()>) {
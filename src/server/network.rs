@@ -1,5 +1,5 @@
 use std::{
-    io,
+    io::{self, Write},
     net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
     ptr,
     sync::{
@@ -12,7 +12,7 @@ use std::{
 
 use pollster::FutureExt;
 
-use crate::world::update::WorldUpdate;
+use crate::world::{position::Dimensions, update::WorldUpdate};
 
 use super::connection::ClientConnection;
 
@@ -23,7 +23,8 @@ pub struct ClientManagerHandle {
 }
 
 impl ClientManagerHandle {
-    pub fn start(addr: SocketAddr) -> io::Result<ClientManagerHandle> {
+    /// starts accepting clients, telling each one the `dims` of the world as it connects
+    pub fn start(addr: SocketAddr, dims: Dimensions) -> io::Result<ClientManagerHandle> {
         let (send, recv) = mpsc::channel();
 
         let updates = Arc::new(Mutex::new(Vec::new()));
@@ -31,7 +32,7 @@ impl ClientManagerHandle {
         let uc = updates.clone();
 
         let jh = thread::spawn(move || {
-            let mgr = ClientManager::new(addr, uc, recv).unwrap();
+            let mgr = ClientManager::new(addr, dims, uc, recv).unwrap();
             mgr.run();
         });
 
@@ -58,6 +59,8 @@ impl ClientManagerHandle {
 }
 
 struct ClientManager {
+    /// sent to every client before anything else
+    dims: Dimensions,
     updates: Arc<Mutex<Vec<WorldUpdate>>>,
     listener: TcpListener,
     stopper: Receiver<()>,
@@ -69,6 +72,7 @@ struct ClientManager {
 impl ClientManager {
     fn new(
         addr: SocketAddr,
+        dims: Dimensions,
         updates: Arc<Mutex<Vec<WorldUpdate>>>,
         stopper: Receiver<()>,
     ) -> io::Result<ClientManager> {
@@ -83,6 +87,7 @@ impl ClientManager {
             .expect("cannot set listener nonblocking");
 
         Ok(ClientManager {
+            dims,
             updates,
             listener,
             stopper,
@@ -95,7 +100,13 @@ impl ClientManager {
     fn run(mut self) {
         loop {
             match self.listener.accept() {
-                Ok((stream, _)) => {
+                Ok((mut stream, _)) => {
+                    let greeting = WorldUpdate::Dimensions(self.dims).to_bytes();
+                    if let Err(e) = stream.write_all(&greeting) {
+                        log::warn!("couldn't send a new client the world's dimensions: {e}");
+                        continue;
+                    }
+
                     let recv_c = self.recv_c.clone();
                     let updates = self.updates.clone();
 
@@ -0,0 +1,172 @@
+//! rock and soil wearing away
+//!
+//! stone left open to the air slowly weathers into dirt, and stone touching
+//! water wears down into dirt too, counting how worn it is in the states
+//! world. The harder it is the longer that takes. Dirt then soaks into mud
+//! (see the `plant` module), and mud left away from water dries out into sand
+//!
+//! weathering and drying are too slow to visit every tick, so they only
+//! happen on random ticks
+
+use rand::Rng;
+
+use crate::world::{
+    block::{BlockKind, Direction},
+    position::{self, Position},
+};
+
+use super::Tick;
+
+/// the chance stone open to the air weathers when randomly ticked
+const WEATHER_CHANCE: f64 = 0.25;
+/// how many ticks water takes to wear through each point of a block's hardness
+pub const WEAR_TICKS: u8 = 20;
+/// how many random ticks mud has to spend away from water before it dries into sand
+pub const DRY_STEPS: u8 = 3;
+
+fn touching_water(tick: &Tick, pos: Position) -> bool {
+    position::neighbors(tick.dims, pos).any(|side| tick.kind(side) == BlockKind::Water)
+}
+
+/// stone with air above it sometimes weathers into dirt
+pub fn weather(tick: &mut Tick, pos: Position) {
+    let above = position::checked_neighbor(tick.dims, pos, Direction::Up);
+    let exposed = above.is_none_or(|above| tick.kind(above) == BlockKind::Air);
+    if exposed && tick.rng.gen_bool(WEATHER_CHANCE) {
+        tick.set(pos, BlockKind::Dirt);
+    }
+}
+
+/// stone touching water counts up until it's worn into dirt. Its count
+/// starts over once it isn't touching water
+pub fn wear(tick: &mut Tick, pos: Position) {
+    if !touching_water(tick, pos) {
+        tick.set_state(pos, 0);
+        return;
+    }
+
+    let worn = tick.get_state(pos) + 1;
+    if worn >= tick.kind(pos).properties().hardness * WEAR_TICKS {
+        tick.set(pos, BlockKind::Dirt);
+    } else {
+        tick.set_state(pos, worn);
+    }
+}
+
+/// mud away from water counts up until it dries into sand. Getting wet again starts it over
+pub fn dry(tick: &mut Tick, pos: Position) {
+    if touching_water(tick, pos) {
+        tick.set_state(pos, 0);
+        return;
+    }
+
+    let dried = tick.get_state(pos) + 1;
+    if dried >= DRY_STEPS {
+        tick.set(pos, BlockKind::Sand);
+    } else {
+        tick.set_state(pos, dried);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{BlockKind, TYPE_DIRT, TYPE_MUD, TYPE_SAND, TYPE_STONE, TYPE_WATER},
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::WEAR_TICKS;
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    /// two layers of stone across chunk 0, randomly ticked for a while
+    fn weathered(seed: u64) -> World {
+        let mut world = World::seeded(seed);
+        let mut states = World::empty();
+        world.set_random_ticks(1024);
+        for pos in position::in_box(DIMS, (0, 0, 0), (15, 1, 15)) {
+            world.get_block_mut(pos).data = TYPE_STONE;
+        }
+        for _ in 0..100 {
+            world.simulate_chunk(&mut states, 0);
+            world.end_tick();
+        }
+        world
+    }
+
+    #[test]
+    fn test_only_exposed_stone_weathers() {
+        let world = weathered(6);
+        let count = |y: i16, data: u8| {
+            position::in_box(DIMS, (0, y, 0), (15, y, 15))
+                .filter(|&pos| world.get_block(pos).data == data)
+                .count()
+        };
+
+        assert!(count(1, TYPE_DIRT) > 0);
+        assert!(count(1, TYPE_STONE) > 0);
+        assert_eq!(count(0, TYPE_STONE), 256);
+
+        let again = weathered(6);
+        assert!(world.blocks == again.blocks);
+    }
+
+    #[test]
+    fn test_water_wears_stone() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        world.set_random_ticks(0);
+        // a pool of water sunk into a slab of stone
+        for pos in position::in_box(DIMS, (3, 0, 3), (7, 1, 7)) {
+            world.get_block_mut(pos).data = TYPE_STONE;
+        }
+        let pool = position::from_xyz(DIMS, 5, 1, 5);
+        world.get_block_mut(pool).data = TYPE_WATER;
+
+        let ticks = BlockKind::Stone.properties().hardness * WEAR_TICKS;
+        let side = position::from_xyz(DIMS, 6, 1, 5);
+        for _ in 1..ticks {
+            world.simulate_chunk(&mut states, 0);
+        }
+        assert_eq!(world.get_block(side).data, TYPE_STONE);
+
+        world.simulate_chunk(&mut states, 0);
+        assert_eq!(world.get_block(side).data, TYPE_DIRT);
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 5, 0, 5)).data,
+            TYPE_DIRT
+        );
+        // only touching the pool at an edge
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 6, 1, 6)).data,
+            TYPE_STONE
+        );
+    }
+
+    #[test]
+    fn test_mud_dries_gradually() {
+        let mut world = World::seeded(2);
+        let mut states = World::empty();
+        world.set_random_ticks(4096);
+        for pos in position::in_box(DIMS, (0, 0, 0), (15, 0, 15)) {
+            world.get_block_mut(pos).data = TYPE_MUD;
+        }
+        let count = |world: &World, data: u8| {
+            position::in_box(DIMS, (0, 0, 0), (15, 0, 15))
+                .filter(|&pos| world.get_block(pos).data == data)
+                .count()
+        };
+
+        world.simulate_chunk(&mut states, 0);
+        world.end_tick();
+        assert_eq!(count(&world, TYPE_SAND), 0);
+
+        for _ in 0..200 {
+            world.simulate_chunk(&mut states, 0);
+            world.end_tick();
+        }
+        assert!(count(&world, TYPE_SAND) > 128);
+        assert_eq!(count(&world, TYPE_SAND) + count(&world, TYPE_MUD), 256);
+    }
+}
@@ -0,0 +1,189 @@
+//! fire
+//!
+//! fire spreads at random into anything flammable next to it and burns out
+//! after a while, on a scheduled tick. Water puts it out
+
+use rand::Rng;
+
+use crate::world::{
+    block::BlockKind,
+    position::{self, Position},
+    schedule::Reason,
+};
+
+use super::Tick;
+
+/// how many ticks fire lasts before burning out
+pub const BURN_TICKS: u64 = 12;
+/// the chance each tick that fire spreads to a given flammable neighbor
+const SPREAD_CHANCE: f64 = 0.5;
+
+/// sets the block on fire if it can burn. Returns whether it caught
+pub fn ignite(tick: &mut Tick, pos: Position) -> bool {
+    let caught = tick.kind(pos).properties().flammable;
+    if caught {
+        tick.set(pos, BlockKind::Fire);
+    }
+    caught
+}
+
+/// spreads to flammable neighbors and destroys steam, staying awake while
+/// there's anything left to spread to. Any water touching the fire puts it out
+pub fn burn(tick: &mut Tick, pos: Position) {
+    let neighbors = position::neighbors(tick.dims, pos).collect::<Vec<_>>();
+
+    if neighbors.iter().any(|&n| tick.kind(n) == BlockKind::Water) {
+        tick.set(pos, BlockKind::Air);
+        return;
+    }
+
+    let mut unburnt = false;
+    for n in neighbors {
+        match tick.kind(n) {
+            BlockKind::Steam => tick.set(n, BlockKind::Air),
+            kind if kind.properties().flammable => {
+                if tick.rng.gen_bool(SPREAD_CHANCE) {
+                    tick.set(n, BlockKind::Fire);
+                } else {
+                    unburnt = true;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if unburnt {
+        tick.wake(pos);
+    }
+    tick.schedule(pos, BURN_TICKS, Reason::BurnOut);
+}
+
+/// the fire's time is up
+pub fn burn_out(tick: &mut Tick, pos: Position) {
+    if tick.kind(pos) == BlockKind::Fire {
+        tick.set(pos, BlockKind::Air);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{TYPE_AIR, TYPE_FIRE, TYPE_WATER, TYPE_WOOD},
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::BURN_TICKS;
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    /// a row of wood along x with fire at one end
+    fn wood_row(seed: u64) -> (World, World) {
+        let mut world = World::seeded(seed);
+        for x in 0..16 {
+            world.get_block_mut(position::from_xyz(DIMS, x, 0, 0)).data = TYPE_WOOD;
+        }
+        world.get_block_mut(position::from_xyz(DIMS, 0, 0, 0)).data = TYPE_FIRE;
+        (world, World::empty())
+    }
+
+    #[test]
+    fn test_burns_out() {
+        let (mut world, mut states) = wood_row(0);
+        world.get_block_mut(position::from_xyz(DIMS, 1, 0, 0)).data = TYPE_AIR;
+
+        for _ in 0..BURN_TICKS {
+            world.simulate_chunk(&mut states, 0);
+            world.end_tick();
+        }
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 0, 0, 0)).data,
+            TYPE_FIRE
+        );
+
+        world.simulate_chunk(&mut states, 0);
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 0, 0, 0)).data,
+            TYPE_AIR
+        );
+    }
+
+    #[test]
+    fn test_relit_burns_for_as_long() {
+        let (mut world, mut states) = wood_row(0);
+        let fire = position::from_xyz(DIMS, 0, 0, 0);
+        world.get_block_mut(position::from_xyz(DIMS, 1, 0, 0)).data = TYPE_AIR;
+        let run = |world: &mut World, states: &mut World, until: u64| {
+            while world.ticks() < until {
+                world.simulate_chunk(states, 0);
+                world.end_tick();
+            }
+        };
+
+        // put out before it burned out, then lit again
+        run(&mut world, &mut states, 5);
+        world.get_block_mut(fire).data = TYPE_AIR;
+        run(&mut world, &mut states, 8);
+        world.get_block_mut(fire).data = TYPE_FIRE;
+        run(&mut world, &mut states, 8 + BURN_TICKS);
+        assert_eq!(world.get_block(fire).data, TYPE_FIRE);
+
+        world.simulate_chunk(&mut states, 0);
+        assert_eq!(world.get_block(fire).data, TYPE_AIR);
+    }
+
+    #[test]
+    fn test_put_out_by_water() {
+        let (mut world, mut states) = wood_row(0);
+        world.get_block_mut(position::from_xyz(DIMS, 0, 1, 0)).data = TYPE_WATER;
+
+        world.simulate_chunk(&mut states, 0);
+
+        assert_ne!(
+            world.get_block(position::from_xyz(DIMS, 0, 0, 0)).data,
+            TYPE_FIRE
+        );
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 1, 0, 0)).data,
+            TYPE_WOOD
+        );
+    }
+
+    #[test]
+    fn test_spread_is_seeded() {
+        let burn = |seed| {
+            let (mut world, mut states) = wood_row(seed);
+            let mut updates = Vec::new();
+            for _ in 0..100 {
+                updates.extend(
+                    world
+                        .simulate_chunk(&mut states, 0)
+                        .updates
+                        .into_iter()
+                        .map(|u| (u.column, u.new_data)),
+                );
+                world.end_tick();
+            }
+            updates
+        };
+
+        assert_eq!(burn(7), burn(7));
+        assert!(burn(7)
+            .iter()
+            .any(|&(column, data)| column == 15 && data == TYPE_FIRE));
+    }
+
+    #[test]
+    fn test_ignite() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        let wood = position::from_xyz(DIMS, 4, 4, 4);
+        world.get_block_mut(wood).data = TYPE_WOOD;
+
+        assert!(world.ignite(&mut states, wood).is_some());
+        assert_eq!(world.get_block(wood).data, TYPE_FIRE);
+        assert!(world
+            .ignite(&mut states, position::from_xyz(DIMS, 5, 4, 4))
+            .is_none());
+    }
+}
@@ -0,0 +1,213 @@
+//! gravity
+//!
+//! loose blocks like sand and seeds fall whenever they can, through air and
+//! through any fluid lighter than they are, which rises to take their place.
+//! Anything else solid stays put as long as something solid holds it up,
+//! either below it or beside it. A block beside it only counts if a row of
+//! solid blocks leads from it, within `REACH` blocks, to one that's resting
+//! on something, so a beam can hang off a pillar but a thick trunk cut at its
+//! base, or a crown left without its trunk, falls the same way. Rows are only
+//! followed sideways, so an arch that climbs before coming back down doesn't
+//! hold anything up
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::world::{
+    block::{BlockKind, Direction},
+    position::{self, Position},
+};
+
+use super::Tick;
+
+/// how many blocks sideways support is followed before giving up
+const REACH: usize = 8;
+
+/// moves the block down one if there's air or a lighter fluid below it. Returns whether it fell
+pub fn fall(tick: &mut Tick, pos: Position) -> bool {
+    let Some(below) = position::checked_neighbor(tick.dims, pos, Direction::Down) else {
+        return false;
+    };
+
+    let (kind, under) = (tick.kind(pos), tick.kind(below));
+    if under == BlockKind::Air {
+        tick.move_block(pos, below);
+        true
+    } else if under.properties().fluid && under.properties().density < kind.properties().density {
+        tick.swap_blocks(pos, below);
+        true
+    } else {
+        false
+    }
+}
+
+/// whether anything solid is holding up the block, either below it or through
+/// a row of solid blocks beside it. The bottom of the world holds up everything on it
+fn supported(tick: &Tick, pos: Position) -> bool {
+    let solid = |pos: Position| tick.kind(pos).properties().solid;
+    let resting = |pos: Position| {
+        position::checked_neighbor(tick.dims, pos, Direction::Down).is_none_or(solid)
+    };
+
+    let mut seen = HashSet::from([pos]);
+    let mut queue = VecDeque::from([(pos, 0)]);
+    while let Some((pos, distance)) = queue.pop_front() {
+        if resting(pos) {
+            return true;
+        }
+        if distance == REACH {
+            continue;
+        }
+        for side in position::horizontal_neighbors(tick.dims, pos) {
+            if solid(side) && seen.insert(side) {
+                queue.push_back((side, distance + 1));
+            }
+        }
+    }
+    false
+}
+
+/// drops a solid block that has lost its support. Returns whether it fell
+pub fn collapse(tick: &mut Tick, pos: Position) -> bool {
+    !supported(tick, pos) && fall(tick, pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{TYPE_AIR, TYPE_LEAVES, TYPE_SAND, TYPE_SEED, TYPE_STONE, TYPE_WATER, TYPE_WOOD},
+        position::{self, Dimensions},
+        World,
+    };
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    fn place(world: &mut World, x: i16, y: i16, z: i16, data: u8) {
+        world.get_block_mut(position::from_xyz(DIMS, x, y, z)).data = data;
+    }
+
+    fn type_at(world: &World, x: i16, y: i16, z: i16) -> u8 {
+        world.get_block(position::from_xyz(DIMS, x, y, z)).data
+    }
+
+    /// simulates the world, checking that a client applying every update in
+    /// order ends up with the same blocks
+    fn simulate_and_follow(world: &mut World, client: &mut World, ticks: usize) {
+        let mut states = World::empty();
+        world.set_random_ticks(0);
+        for _ in 0..ticks {
+            for update in world.simulate(&mut states).updates {
+                client.process_update(update);
+            }
+        }
+        assert!(world.blocks == client.blocks);
+    }
+
+    #[test]
+    fn test_sinks_by_density() {
+        let mut world = World::empty();
+        let mut client = World::empty();
+        for world in [&mut world, &mut client] {
+            // wells of water three deep
+            for pos in position::in_box(DIMS, (1, 0, 1), (7, 2, 7)) {
+                world.get_block_mut(pos).data = TYPE_STONE;
+            }
+            for y in 0..3 {
+                place(world, 2, y, 2, TYPE_WATER);
+                place(world, 4, y, 4, TYPE_WATER);
+                place(world, 6, y, 6, TYPE_WATER);
+            }
+            place(world, 2, 5, 2, TYPE_SAND);
+            place(world, 4, 5, 4, TYPE_SEED);
+            place(world, 6, 5, 6, TYPE_WOOD);
+        }
+        simulate_and_follow(&mut world, &mut client, 10);
+
+        // sand and seeds are heavier than water, but wood floats
+        assert_eq!(type_at(&world, 2, 0, 2), TYPE_SAND);
+        assert_eq!(type_at(&world, 2, 3, 2), TYPE_WATER);
+        assert_eq!(type_at(&world, 4, 0, 4), TYPE_SEED);
+        assert_eq!(type_at(&world, 6, 3, 6), TYPE_WOOD);
+        assert_eq!(type_at(&world, 6, 2, 6), TYPE_WATER);
+    }
+
+    #[test]
+    fn test_cut_column_collapses() {
+        let mut world = World::empty();
+        let mut client = World::empty();
+        for world in [&mut world, &mut client] {
+            place(world, 3, 0, 3, TYPE_STONE);
+            for y in 1..=6 {
+                place(world, 3, y, 3, TYPE_WOOD);
+            }
+            // a beam held up from the side
+            place(world, 4, 6, 3, TYPE_WOOD);
+            place(world, 5, 6, 3, TYPE_WOOD);
+            for y in 0..=6 {
+                place(world, 6, y, 3, TYPE_STONE);
+            }
+            place(world, 3, 1, 3, TYPE_AIR);
+        }
+        simulate_and_follow(&mut world, &mut client, 10);
+
+        // the column fell onto the stone, leaving its top held up by the beam
+        for y in 1..=4 {
+            assert_eq!(type_at(&world, 3, y, 3), TYPE_WOOD);
+        }
+        assert_eq!(type_at(&world, 3, 5, 3), TYPE_AIR);
+        assert_eq!(type_at(&world, 3, 6, 3), TYPE_WOOD);
+        assert_eq!(type_at(&world, 4, 6, 3), TYPE_WOOD);
+        assert_eq!(type_at(&world, 5, 6, 3), TYPE_WOOD);
+    }
+
+    #[test]
+    fn test_thick_trunk_collapses() {
+        let mut world = World::empty();
+        let mut client = World::empty();
+        for world in [&mut world, &mut client] {
+            for pos in position::in_box(DIMS, (3, 0, 3), (4, 0, 4)) {
+                world.get_block_mut(pos).data = TYPE_STONE;
+            }
+            for pos in position::in_box(DIMS, (3, 2, 3), (4, 6, 4)) {
+                world.get_block_mut(pos).data = TYPE_WOOD;
+            }
+        }
+        simulate_and_follow(&mut world, &mut client, 10);
+
+        // the four halves of the trunk only held each other up
+        for pos in position::in_box(DIMS, (3, 1, 3), (4, 5, 4)) {
+            assert_eq!(world.get_block(pos).data, TYPE_WOOD);
+        }
+        for pos in position::in_box(DIMS, (3, 6, 3), (4, 6, 4)) {
+            assert_eq!(world.get_block(pos).data, TYPE_AIR);
+        }
+    }
+
+    #[test]
+    fn test_crown_follows_its_trunk() {
+        let mut world = World::empty();
+        let mut client = World::empty();
+        for world in [&mut world, &mut client] {
+            place(world, 10, 0, 10, TYPE_STONE);
+            for pos in position::in_box(DIMS, (9, 4, 9), (11, 5, 11)) {
+                world.get_block_mut(pos).data = TYPE_LEAVES;
+            }
+            for y in 2..=4 {
+                place(world, 10, y, 10, TYPE_WOOD);
+            }
+        }
+        simulate_and_follow(&mut world, &mut client, 10);
+
+        // the crown fell with the trunk and now sits one lower, on its top
+        for y in 1..=3 {
+            assert_eq!(type_at(&world, 10, y, 10), TYPE_WOOD);
+        }
+        for pos in position::in_box(DIMS, (9, 3, 9), (11, 4, 11)) {
+            if pos != position::from_xyz(DIMS, 10, 3, 10) {
+                assert_eq!(world.get_block(pos).data, TYPE_LEAVES);
+            }
+        }
+        for pos in position::in_box(DIMS, (9, 5, 9), (11, 5, 11)) {
+            assert_eq!(world.get_block(pos).data, TYPE_AIR);
+        }
+    }
+}
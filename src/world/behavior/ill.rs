@@ -0,0 +1,292 @@
+//! the ill
+//!
+//! the ill spreads through the world in waves, every so many ticks, taking
+//! over whatever it reaches. Each wave every block of ill has a chance to
+//! spread into each block beside it, and tougher materials hold out for longer.
+//! It can't cross some blocks at all, like air and water. Ill that has nothing
+//! left to spread into stops waiting on waves until something beside it changes
+//!
+//! the ill taking a shrine is a [`GameEvent`]
+//!
+//! [`GameEvent`]: crate::world::GameEvent
+
+use std::{error::Error, fmt};
+
+use rand::Rng;
+
+use crate::world::{
+    block::BlockKind,
+    position::{self, Position},
+    schedule::Reason,
+    GameEvent,
+};
+
+use super::Tick;
+
+/// how the ill spreads
+#[derive(Debug, Clone, PartialEq)]
+pub struct IllRules {
+    /// how many ticks go by between waves
+    pub wave_ticks: u64,
+    /// the chance each wave that the ill spreads into a block beside it with
+    /// a resistance of 1
+    pub spread_chance: f64,
+    /// how hard it is for the ill to spread into each kind of block, indexed
+    /// by type number. The chance of spreading is divided by this, and the
+    /// ill can't spread into kinds with a resistance of 0
+    pub resistance: [u8; 16],
+}
+
+impl Default for IllRules {
+    fn default() -> Self {
+        let mut resistance = [0; 16];
+        for (kind, value) in [
+            (BlockKind::Dirt, 1),
+            (BlockKind::Mud, 1),
+            (BlockKind::Sand, 1),
+            (BlockKind::Leaves, 1),
+            (BlockKind::Seed, 1),
+            (BlockKind::Wood, 2),
+            (BlockKind::Shrine, 2),
+            (BlockKind::Stone, 4),
+            (BlockKind::Door, 4),
+            (BlockKind::Cannon, 4),
+        ] {
+            resistance[kind as usize] = value;
+        }
+
+        IllRules {
+            wave_ticks: 20,
+            spread_chance: 0.5,
+            resistance,
+        }
+    }
+}
+
+impl IllRules {
+    /// waves have to be at least a tick apart, and the spread chance between 0 and 1
+    pub fn validate(self) -> Result<IllRules, InvalidIllRules> {
+        if self.wave_ticks > 0 && (0.0..=1.0).contains(&self.spread_chance) {
+            Ok(self)
+        } else {
+            Err(InvalidIllRules(self))
+        }
+    }
+}
+
+/// rules that break one of the requirements in [`IllRules::validate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidIllRules(pub IllRules);
+
+impl fmt::Display for InvalidIllRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid ill rules with waves every {} ticks and a spread chance of {}",
+            self.0.wave_ticks, self.0.spread_chance
+        )
+    }
+}
+
+impl Error for InvalidIllRules {}
+
+/// the ill waits for the next wave, as long as there's anything beside it to spread into
+pub fn wait(tick: &mut Tick, pos: Position) {
+    if !prey(tick, pos).is_empty() {
+        let wave_ticks = tick.world.ill_rules.wave_ticks;
+        tick.schedule(
+            pos,
+            wave_ticks - tick.world.ticks % wave_ticks,
+            Reason::Spread,
+        );
+    }
+}
+
+/// one wave of the ill spreading out from the block
+pub fn spread(tick: &mut Tick, pos: Position) {
+    if tick.kind(pos) != BlockKind::Ill {
+        return;
+    }
+
+    let world = tick.world;
+    let rules = &world.ill_rules;
+    for side in prey(tick, pos) {
+        let kind = tick.kind(side);
+        let chance = rules.spread_chance / rules.resistance[kind as usize] as f64;
+        if tick.rng.gen_bool(chance) {
+            if kind == BlockKind::Shrine {
+                tick.changes.events.push(GameEvent::ShrineReached(side));
+            }
+            tick.set(side, BlockKind::Ill);
+        }
+    }
+    wait(tick, pos);
+}
+
+/// the blocks beside the ill that it could spread into
+fn prey(tick: &Tick, pos: Position) -> Vec<Position> {
+    let resistance = &tick.world.ill_rules.resistance;
+    position::neighbors(tick.dims, pos)
+        .filter(|&side| resistance[tick.kind(side) as usize] > 0 && !tick.touched(side))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{TYPE_DIRT, TYPE_ILL, TYPE_SHRINE, TYPE_STONE, TYPE_WATER},
+        position::{self, Dimensions},
+        GameEvent, World,
+    };
+
+    use super::IllRules;
+
+    /// a world with a floor of `floor` and a block of ill in the middle of it
+    fn infected(floor: u8, seed: u64) -> World {
+        let dims = Dimensions::new(32, 8, 8).unwrap();
+        let mut world = World::with_dimensions(dims, seed);
+        world.set_random_ticks(0);
+        for pos in position::in_box(dims, (0, 0, 0), (31, 0, 31)) {
+            world.get_block_mut(pos).data = floor;
+        }
+        world
+            .get_block_mut(position::from_xyz(dims, 16, 0, 16))
+            .data = TYPE_ILL;
+        world
+    }
+
+    fn ill_count(world: &World) -> usize {
+        let dims = world.dimensions();
+        position::in_box(dims, (0, 0, 0), (31, 7, 31))
+            .filter(|&pos| world.get_block(pos).data == TYPE_ILL)
+            .count()
+    }
+
+    fn run(world: &mut World, ticks: u64) {
+        let mut states = World::with_dimensions(world.dimensions(), 0);
+        while world.ticks() < ticks {
+            world.simulate(&mut states);
+        }
+    }
+
+    #[test]
+    fn test_spreads_in_waves() {
+        let mut world = infected(TYPE_DIRT, 0);
+        let wave_ticks = 10;
+        world
+            .set_ill_rules(IllRules {
+                wave_ticks,
+                spread_chance: 1.0,
+                ..IllRules::default()
+            })
+            .unwrap();
+
+        // certain to spread, so each wave grows a diamond over the floor by one
+        for wave in 1..=3 {
+            run(&mut world, wave * wave_ticks);
+            let radius = wave as usize - 1;
+            assert_eq!(ill_count(&world), 2 * radius * radius + 2 * radius + 1);
+            run(&mut world, wave * wave_ticks + 1);
+            let radius = wave as usize;
+            assert_eq!(ill_count(&world), 2 * radius * radius + 2 * radius + 1);
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_rules() {
+        let mut world = infected(TYPE_DIRT, 0);
+        for rules in [
+            IllRules {
+                wave_ticks: 0,
+                ..IllRules::default()
+            },
+            IllRules {
+                spread_chance: -0.5,
+                ..IllRules::default()
+            },
+            IllRules {
+                spread_chance: f64::NAN,
+                ..IllRules::default()
+            },
+        ] {
+            assert!(world.set_ill_rules(rules).is_err());
+        }
+        assert_eq!(*world.ill_rules(), IllRules::default());
+        run(&mut world, 100);
+    }
+
+    #[test]
+    fn test_seeded_and_slowed_by_stone() {
+        let spread = |floor: u8, seed: u64| {
+            let mut world = infected(floor, seed);
+            run(&mut world, 300);
+            world
+        };
+
+        let (first, again, other) = (
+            spread(TYPE_DIRT, 1),
+            spread(TYPE_DIRT, 1),
+            spread(TYPE_DIRT, 2),
+        );
+        assert!(first.blocks == again.blocks);
+        assert!(first.blocks != other.blocks);
+        // it never leaves the floor for the air
+        assert_eq!(
+            ill_count(&first),
+            position::in_box(first.dimensions(), (0, 0, 0), (31, 0, 31))
+                .filter(|&pos| first.get_block(pos).data == TYPE_ILL)
+                .count()
+        );
+
+        let stone = spread(TYPE_STONE, 1);
+        assert!(ill_count(&stone) * 2 < ill_count(&first));
+    }
+
+    #[test]
+    fn test_reaching_a_shrine() {
+        let mut world = infected(TYPE_DIRT, 0);
+        let dims = world.dimensions();
+        // moats keep the ill on the shrine's side of the world, either way around
+        for x in [14, 30] {
+            for pos in position::in_box(dims, (x, 0, 0), (x, 0, 31)) {
+                world.get_block_mut(pos).data = TYPE_WATER;
+            }
+        }
+        let shrine = position::from_xyz(dims, 20, 0, 16);
+        world.get_block_mut(shrine).data = TYPE_SHRINE;
+
+        let mut states = World::with_dimensions(dims, 0);
+        let mut events = Vec::new();
+        while world.ticks() < 1000 {
+            events.extend(world.simulate(&mut states).events);
+        }
+        assert_eq!(events, [GameEvent::ShrineReached(shrine)]);
+        assert_eq!(world.get_block(shrine).data, TYPE_ILL);
+        let beyond = position::from_xyz(dims, 10, 0, 16);
+        assert_eq!(world.get_block(beyond).data, TYPE_DIRT);
+    }
+
+    #[test]
+    fn test_reaching_a_shrine_in_one_chunk() {
+        let mut world = infected(TYPE_DIRT, 0);
+        let dims = world.dimensions();
+        world
+            .set_ill_rules(IllRules {
+                wave_ticks: 1,
+                spread_chance: 1.0,
+                ..IllRules::default()
+            })
+            .unwrap();
+        world.get_block_mut(position::from_xyz(dims, 2, 0, 2)).data = TYPE_ILL;
+        let shrine = position::from_xyz(dims, 3, 0, 2);
+        world.get_block_mut(shrine).data = TYPE_SHRINE;
+
+        let mut states = World::with_dimensions(dims, 0);
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            events.extend(world.simulate_chunk(&mut states, 0).events);
+            world.end_tick();
+        }
+        assert_eq!(events, [GameEvent::ShrineReached(shrine)]);
+    }
+}
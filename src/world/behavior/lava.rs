@@ -0,0 +1,196 @@
+//! lava
+//!
+//! lava reacts with whatever it touches: water quenches it into stone,
+//! sand and dirt melt into more lava and anything flammable catches fire.
+//! Lava left touching air cools until it hardens into stone on a scheduled
+//! tick. Covering it up cancels the tick, so it starts cooling over again
+//! once it's back in the open
+
+use rand::Rng;
+
+use crate::world::{
+    block::BlockKind,
+    position::{self, Position},
+    schedule::Reason,
+};
+
+use super::{fire, gravity, Tick};
+
+/// how many ticks in a row lava can spend touching air before it hardens
+pub const COOL_TICKS: u64 = 200;
+/// the chance each tick that lava melts a given neighboring sand or dirt block
+const MELT_CHANCE: f64 = 0.05;
+
+/// applies lava's interactions with each of its neighbors, then cools and flows downward
+pub fn flow(tick: &mut Tick, pos: Position) {
+    let mut exposed = false;
+
+    for side in position::neighbors(tick.dims, pos) {
+        match tick.kind(side) {
+            BlockKind::Water => {
+                quench(tick, pos, side);
+                return;
+            }
+            BlockKind::Sand | BlockKind::Dirt => melt(tick, pos, side),
+            BlockKind::Air => exposed = true,
+            _ => {
+                fire::ignite(tick, side);
+            }
+        }
+    }
+
+    if exposed {
+        tick.schedule(pos, COOL_TICKS, Reason::Harden);
+    } else {
+        tick.unschedule(pos, Reason::Harden);
+    }
+
+    if tick.kind(pos) == BlockKind::Lava {
+        gravity::fall(tick, pos);
+    }
+}
+
+/// water hardens lava into stone and boils away into steam
+fn quench(tick: &mut Tick, lava: Position, water: Position) {
+    tick.set(water, BlockKind::Steam);
+    tick.set(lava, BlockKind::Stone);
+}
+
+/// sand and dirt sometimes melt into lava
+fn melt(tick: &mut Tick, lava: Position, target: Position) {
+    if tick.rng.gen_bool(MELT_CHANCE) {
+        tick.set(target, BlockKind::Lava);
+    } else {
+        tick.wake(lava);
+    }
+}
+
+/// lava that has cooled in the open for long enough hardens, as long as it's still in the open
+pub fn harden(tick: &mut Tick, lava: Position) {
+    let exposed =
+        position::neighbors(tick.dims, lava).any(|side| tick.kind(side) == BlockKind::Air);
+    if tick.kind(lava) == BlockKind::Lava && exposed {
+        tick.set(lava, BlockKind::Stone);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{
+            TYPE_DIRT, TYPE_FIRE, TYPE_LAVA, TYPE_LEAVES, TYPE_SAND, TYPE_STEAM, TYPE_STONE,
+            TYPE_WATER, TYPE_WOOD,
+        },
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::COOL_TICKS;
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    /// lava at (8, 1, 8) on a stone floor with `neighbor` to its east,
+    /// sealed off from the air everywhere else so it doesn't cool
+    fn lava_next_to(neighbor: u8) -> (World, World) {
+        let mut world = World::empty();
+        for x in 7..=9 {
+            for y in 0..=2 {
+                for z in 7..=9 {
+                    world.get_block_mut(position::from_xyz(DIMS, x, y, z)).data = TYPE_STONE;
+                }
+            }
+        }
+        world.get_block_mut(position::from_xyz(DIMS, 8, 1, 8)).data = TYPE_LAVA;
+        world.get_block_mut(position::from_xyz(DIMS, 9, 1, 8)).data = neighbor;
+        (world, World::empty())
+    }
+
+    /// runs ticks until the block east of the lava isn't `neighbor` anymore
+    fn react(neighbor: u8) -> (u8, u8) {
+        let (mut world, mut states) = lava_next_to(neighbor);
+        for _ in 0..1000 {
+            world.simulate_chunk(&mut states, 0);
+            world.end_tick();
+            if world.get_block(position::from_xyz(DIMS, 9, 1, 8)).data != neighbor {
+                break;
+            }
+        }
+        (
+            world.get_block(position::from_xyz(DIMS, 8, 1, 8)).data,
+            world.get_block(position::from_xyz(DIMS, 9, 1, 8)).data,
+        )
+    }
+
+    #[test]
+    fn test_water_makes_stone_and_steam() {
+        assert_eq!(react(TYPE_WATER), (TYPE_STONE, TYPE_STEAM));
+    }
+
+    #[test]
+    fn test_melts_sand() {
+        assert_eq!(react(TYPE_SAND), (TYPE_LAVA, TYPE_LAVA));
+    }
+
+    #[test]
+    fn test_melts_dirt() {
+        assert_eq!(react(TYPE_DIRT), (TYPE_LAVA, TYPE_LAVA));
+    }
+
+    #[test]
+    fn test_ignites_wood() {
+        assert_eq!(react(TYPE_WOOD), (TYPE_LAVA, TYPE_FIRE));
+    }
+
+    #[test]
+    fn test_ignites_leaves() {
+        assert_eq!(react(TYPE_LEAVES), (TYPE_LAVA, TYPE_FIRE));
+    }
+
+    #[test]
+    fn test_cools_in_air() {
+        let (mut world, mut states) = lava_next_to(TYPE_STONE);
+        world.get_block_mut(position::from_xyz(DIMS, 8, 2, 8)).data = 0;
+
+        for _ in 0..COOL_TICKS {
+            world.simulate_chunk(&mut states, 0);
+            world.end_tick();
+        }
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 8, 1, 8)).data,
+            TYPE_LAVA
+        );
+
+        world.simulate_chunk(&mut states, 0);
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 8, 1, 8)).data,
+            TYPE_STONE
+        );
+    }
+
+    #[test]
+    fn test_covered_and_uncovered() {
+        let (mut world, mut states) = lava_next_to(TYPE_STONE);
+        let lava = position::from_xyz(DIMS, 8, 1, 8);
+        let above = position::from_xyz(DIMS, 8, 2, 8);
+        let run = |world: &mut World, states: &mut World, until: u64| {
+            while world.ticks() < until {
+                world.simulate_chunk(states, 0);
+                world.end_tick();
+            }
+        };
+
+        // out in the open for a tick, then covered until nearly when it would have hardened
+        world.get_block_mut(above).data = 0;
+        run(&mut world, &mut states, 1);
+        world.get_block_mut(above).data = TYPE_STONE;
+        run(&mut world, &mut states, 198);
+        assert_eq!(world.scheduled().count(), 0);
+
+        // so it has to cool all over again
+        world.get_block_mut(above).data = 0;
+        run(&mut world, &mut states, 198 + COOL_TICKS);
+        assert_eq!(world.get_block(lava).data, TYPE_LAVA);
+        world.simulate_chunk(&mut states, 0);
+        assert_eq!(world.get_block(lava).data, TYPE_STONE);
+    }
+}
@@ -0,0 +1,680 @@
+//! block behaviors
+//!
+//! every block in a chunk gets a chance to act once per tick. Blocks are
+//! visited column by column, bottom to top, so a tick is deterministic
+//!
+//! alongside the visible block data, each block has a byte in the states world
+//! for whatever its behavior needs to track. It is reset whenever the block changes
+//!
+//! most of the world sits still, so a tick only visits columns that were woken
+//! during the last one, along with the columns beside them. Changing a block or
+//! its state wakes it, and blocks waiting on something can wake themselves.
+//! Blocks with nothing to do for a while schedule a tick instead (see the
+//! `schedule` module). Those are handled at the start of their chunk's tick,
+//! before any columns are visited
+//!
+//! processes too slow to be worth visiting every tick, like weathering, happen
+//! on random ticks instead. At the end of every tick each chunk picks a few
+//! blocks at random, whether it's active or not, and gives them a chance to act
+//!
+//! no rule changes a block further than one from the block acting, so chunks
+//! are ticked in four phases of a checkerboard over the chunk grid. Chunks in
+//! the same phase are never next to each other, so they can't change the same
+//! blocks and are ticked in parallel. Their changes are applied in chunk order
+//! once the phase is done, so the result doesn't depend on the thread count.
+//! A block changed in one phase doesn't act again in a later one, so nothing
+//! moves further across a chunk border than it would inside a chunk
+//!
+//! some rules read further than they change, like gravity following a row of
+//! blocks sideways to find what holds them up, and can look into another chunk
+//! in the same phase. They only ever see the world as it was before the phase,
+//! plus their own chunk's changes, so what they read never depends on the
+//! order chunks run in. It may be a phase out of date, but whatever changed
+//! wakes the blocks beside it, so anything that relied on it is visited again
+//! next tick and catches up
+
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    num::NonZeroUsize,
+    thread,
+};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+mod erosion;
+mod fire;
+mod gravity;
+mod ill;
+mod lava;
+mod plant;
+mod water;
+
+pub use ill::{IllRules, InvalidIllRules};
+
+use super::{
+    block::{Block, BlockKind, BlockUpdate, Direction},
+    position::{self, Dimensions, Position},
+    schedule::{Reason, ScheduledTick},
+    temperature, weather, GameEvent, TickReport, World,
+};
+
+/// bookkeeping for a single tick of a single chunk
+struct Tick<'a> {
+    dims: Dimensions,
+    world: &'a World,
+    states: &'a World,
+    /// blocks that chunks in earlier phases already changed this tick
+    earlier: &'a HashSet<Position>,
+    changes: Changes,
+    /// source of every random roll made during the tick
+    rng: ChaCha8Rng,
+}
+
+/// everything a tick changed, kept aside until the tick is applied to the world
+#[derive(Default)]
+struct Changes {
+    /// blocks that changed this tick. They don't act again until the next one
+    blocks: HashMap<Position, Block>,
+    states: HashMap<Position, u8>,
+    woken: Vec<Position>,
+    scheduled: Vec<ScheduledTick>,
+    /// ticks cancelled, by the block that was waiting on them and why
+    unscheduled: Vec<(Position, Reason)>,
+    /// steam that rose into the clouds, by the chunk it was in
+    clouds: Vec<u8>,
+    updates: Vec<BlockUpdate>,
+    events: Vec<GameEvent>,
+}
+
+impl Changes {
+    /// writes the changes into the world, returning the updates to send out
+    fn apply(self, world: &mut World, states: &mut World) -> Vec<BlockUpdate> {
+        // every position appears once, so the order these are written in doesn't matter
+        for (pos, block) in self.blocks {
+            *world.get_block_mut(pos) = block;
+        }
+        for (pos, state) in self.states {
+            states.get_block_mut(pos).data = state;
+        }
+        for pos in self.woken {
+            world.wake(pos);
+        }
+        for (pos, reason) in self.unscheduled {
+            world.schedule.remove(pos, reason);
+        }
+        for scheduled in self.scheduled {
+            world.schedule.add(scheduled);
+        }
+        for chunk in self.clouds {
+            world.weather.gather(chunk);
+        }
+        self.updates
+    }
+}
+
+impl<'a> Tick<'a> {
+    fn new(
+        world: &'a World,
+        states: &'a World,
+        earlier: &'a HashSet<Position>,
+        chunk: u8,
+    ) -> Tick<'a> {
+        Tick {
+            dims: world.dims,
+            world,
+            states,
+            earlier,
+            changes: Changes::default(),
+            rng: chunk_rng(world, chunk, false),
+        }
+    }
+
+    fn block(&self, pos: Position) -> Block {
+        match self.changes.blocks.get(&pos) {
+            Some(&block) => block,
+            None => *self.world.get_block(pos),
+        }
+    }
+
+    fn kind(&self, pos: Position) -> BlockKind {
+        self.block(pos).kind()
+    }
+
+    /// how hot the block's cell was at the start of the tick
+    fn temperature(&self, pos: Position) -> u8 {
+        self.world.temperature.at(pos)
+    }
+
+    fn get_state(&self, pos: Position) -> u8 {
+        match self.changes.states.get(&pos) {
+            Some(&state) => state,
+            None => self.states.get_block(pos).data,
+        }
+    }
+
+    /// whether the block already changed this tick, here or in an earlier phase
+    fn touched(&self, pos: Position) -> bool {
+        self.changes.blocks.contains_key(&pos) || self.earlier.contains(&pos)
+    }
+
+    /// replaces the block with an unrotated one of the given kind, clearing its state
+    fn set(&mut self, pos: Position, kind: BlockKind) {
+        self.put(pos, Block::from(kind));
+    }
+
+    /// replaces the block, clearing its state
+    fn put(&mut self, pos: Position, block: Block) {
+        self.changes.blocks.insert(pos, block);
+        self.changes.states.insert(pos, 0);
+        let update = BlockUpdate::new(self.dims, pos, block.data);
+        self.changes.updates.push(update);
+    }
+
+    /// state changes are internal, so unlike [`Tick::set`] nothing is sent out
+    fn set_state(&mut self, pos: Position, state: u8) {
+        if self.get_state(pos) != state {
+            self.changes.states.insert(pos, state);
+            self.wake(pos);
+        }
+    }
+
+    /// visit the block next tick even though nothing about it changed
+    fn wake(&mut self, pos: Position) {
+        self.changes.woken.push(pos);
+    }
+
+    /// tick the block again in `delay` ticks, unless it's already waiting on one for `reason`
+    fn schedule(&mut self, pos: Position, delay: u64, reason: Reason) {
+        self.changes.scheduled.push(ScheduledTick {
+            tick: self.world.ticks + delay,
+            pos,
+            reason,
+        });
+    }
+
+    /// cancels the block's tick for `reason`, if it's waiting on one
+    fn unschedule(&mut self, pos: Position, reason: Reason) {
+        self.changes.unscheduled.push((pos, reason));
+    }
+
+    /// swaps the blocks and their states
+    fn swap_blocks(&mut self, a: Position, b: Position) {
+        let (block_a, state_a) = (self.block(a), self.get_state(a));
+        let (block_b, state_b) = (self.block(b), self.get_state(b));
+        self.put(b, block_a);
+        self.set_state(b, state_a);
+        self.put(a, block_b);
+        self.set_state(a, state_b);
+    }
+
+    /// moves the block and its state from `from` to `to`, leaving air behind
+    fn move_block(&mut self, from: Position, to: Position) {
+        let block = self.block(from);
+        let state = self.get_state(from);
+        self.put(to, block);
+        self.set_state(to, state);
+        self.set(from, BlockKind::Air);
+    }
+}
+
+/// how many blocks each chunk randomly ticks every tick, unless the world says otherwise
+pub const DEFAULT_RANDOM_TICKS: u16 = 64;
+
+/// the rolls for this tick of a chunk. Each tick of each chunk gets its own
+/// stream, so rolls stay repeatable no matter which order chunks are simulated
+/// in. Random ticks get streams of their own, so which blocks they pick doesn't
+/// depend on how many rolls the rest of the tick made
+fn chunk_rng(world: &World, chunk: u8, random_ticks: bool) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(world.seed);
+    let stream = world.ticks * 256 + chunk as u64;
+    rng.set_stream(stream | (random_ticks as u64) << 63);
+    rng
+}
+
+/// how many threads [`World::simulate`] uses by default
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// one chunk's share of a tick
+struct Job {
+    chunk: u8,
+    /// the columns in the chunk to visit
+    columns: Vec<u8>,
+    /// scheduled ticks in the chunk that are due, in order
+    due: Vec<ScheduledTick>,
+}
+
+/// which of the four checkerboard phases a chunk is ticked in
+fn phase(dims: Dimensions, chunk: u8) -> u8 {
+    let (x, z) = (
+        chunk as u16 % dims.chunks_across(),
+        chunk as u16 / dims.chunks_across(),
+    );
+    (x & 1 | (z & 1) << 1) as u8
+}
+
+/// runs one tick for the whole world, visiting the columns woken during the
+/// last tick and their neighbors, along with every scheduled tick that's due,
+/// then randomly ticks every chunk and updates the weather. The result is the same for any number of `threads`
+pub fn simulate(world: &mut World, states: &mut World, threads: usize) -> TickReport {
+    let dims = world.dims;
+    let woken = mem::replace(&mut world.active, vec![false; dims.columns()]);
+
+    // a column's index times the height is the position of its bottom block
+    let height = dims.height() as usize;
+    let mut visit = woken.clone();
+    for (column, _) in woken.iter().enumerate().filter(|(_, &woken)| woken) {
+        for side in position::horizontal_neighbors(dims, column * height) {
+            visit[side / height] = true;
+        }
+    }
+
+    let mut due = vec![Vec::new(); dims.chunks()];
+    for scheduled in world.schedule.take_due(world.ticks, |_| true) {
+        due[position::chunk(dims, scheduled.pos) as usize].push(scheduled);
+    }
+
+    let mut report = TickReport {
+        updates: Vec::new(),
+        events: Vec::new(),
+        chunks: 0,
+    };
+    // blocks changed in one phase don't act again in a later one
+    let mut touched = HashSet::new();
+    for current in 0..4 {
+        let jobs: Vec<Job> = visit
+            .chunks(dims.chunk_columns())
+            .enumerate()
+            .zip(&mut due)
+            .map(|((chunk, columns), due)| (chunk as u8, columns, due))
+            .filter(|(chunk, columns, due)| {
+                let busy = columns.contains(&true) || !due.is_empty();
+                phase(dims, *chunk) == current && (busy || world.random_ticks > 0)
+            })
+            .map(|(chunk, columns, due)| {
+                let columns = (0..columns.len()).filter(|&column| columns[column]);
+                Job {
+                    chunk,
+                    columns: columns.map(|column| column as u8).collect(),
+                    due: mem::take(due),
+                }
+            })
+            .collect();
+
+        let busy = jobs
+            .iter()
+            .filter(|job| !job.columns.is_empty() || !job.due.is_empty());
+        report.chunks += busy.count();
+        for mut changes in tick_phase(world, states, &touched, &jobs, threads) {
+            touched.extend(changes.blocks.keys().copied());
+            report.events.append(&mut changes.events);
+            report.updates.extend(changes.apply(world, states));
+        }
+    }
+
+    report.updates.extend(weather::update(world, states));
+    temperature::update(world);
+    world.end_tick();
+    report
+}
+
+/// ticks the chunks of one phase, spread over up to `threads` threads.
+/// Changes come back in the same order as `jobs`
+fn tick_phase(
+    world: &World,
+    states: &World,
+    earlier: &HashSet<Position>,
+    jobs: &[Job],
+    threads: usize,
+) -> Vec<Changes> {
+    let tick = |job: &Job| {
+        tick_chunk(
+            world,
+            states,
+            earlier,
+            job.chunk,
+            &job.due,
+            job.columns.iter().copied(),
+        )
+    };
+
+    if threads <= 1 || jobs.len() <= 1 {
+        return jobs.iter().map(tick).collect();
+    }
+
+    let per_thread = jobs.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = jobs
+            .chunks(per_thread)
+            .map(|batch| scope.spawn(move || batch.iter().map(tick).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("chunk tick panicked"))
+            .collect()
+    })
+}
+
+/// runs one tick for every block in the given columns of a chunk, along with
+/// the chunk's scheduled ticks that are due and its random ticks, returning what changed
+/// and what happened
+pub fn simulate_chunk(
+    world: &mut World,
+    states: &mut World,
+    chunk: u8,
+    columns: impl IntoIterator<Item = u8>,
+) -> TickReport {
+    let dims = world.dims;
+    let due = world
+        .schedule
+        .take_due(world.ticks, |pos| position::chunk(dims, pos) == chunk);
+    let mut changes = tick_chunk(world, states, &HashSet::new(), chunk, &due, columns);
+    let events = mem::take(&mut changes.events);
+    TickReport {
+        updates: changes.apply(world, states),
+        events,
+        chunks: 1,
+    }
+}
+
+/// works out what one tick of the given columns of a chunk changes, without changing anything
+fn tick_chunk(
+    world: &World,
+    states: &World,
+    earlier: &HashSet<Position>,
+    chunk: u8,
+    due: &[ScheduledTick],
+    columns: impl IntoIterator<Item = u8>,
+) -> Changes {
+    let mut tick = Tick::new(world, states, earlier, chunk);
+
+    for scheduled in due {
+        if tick.touched(scheduled.pos) {
+            continue;
+        }
+        match scheduled.reason {
+            Reason::Sprout => plant::sprout(&mut tick, scheduled.pos),
+            Reason::Spread => ill::spread(&mut tick, scheduled.pos),
+            Reason::BurnOut => fire::burn_out(&mut tick, scheduled.pos),
+            Reason::Harden => lava::harden(&mut tick, scheduled.pos),
+        }
+    }
+
+    for column in columns {
+        for block in 0..world.dims.height() {
+            let pos = position::from_ccb(world.dims, chunk, column, block as u8);
+            if tick.touched(pos) {
+                continue;
+            }
+
+            let kind = tick.kind(pos);
+            // sand always falls, but anything else solid only falls once nothing holds it up
+            if kind.properties().solid
+                && kind != BlockKind::Sand
+                && gravity::collapse(&mut tick, pos)
+            {
+                continue;
+            }
+
+            match kind {
+                BlockKind::Water => water::flow(&mut tick, pos),
+                BlockKind::Sand => {
+                    gravity::fall(&mut tick, pos);
+                }
+                BlockKind::Dirt => plant::soak(&mut tick, pos),
+                BlockKind::Stone => erosion::wear(&mut tick, pos),
+                BlockKind::Seed => plant::germinate(&mut tick, pos),
+                BlockKind::Wood => plant::grow(&mut tick, pos),
+                BlockKind::Steam => rise(&mut tick, pos),
+                BlockKind::Lava => lava::flow(&mut tick, pos),
+                BlockKind::Fire => fire::burn(&mut tick, pos),
+                BlockKind::Ill => ill::wait(&mut tick, pos),
+                _ => (),
+            }
+        }
+    }
+
+    if world.random_ticks > 0 {
+        tick.rng = chunk_rng(world, chunk, true);
+    }
+    for _ in 0..world.random_ticks {
+        let column = tick.rng.gen_range(0..world.dims.chunk_columns()) as u8;
+        let block = tick.rng.gen_range(0..world.dims.height()) as u8;
+        let pos = position::from_ccb(world.dims, chunk, column, block);
+        if tick.touched(pos) {
+            continue;
+        }
+
+        match tick.kind(pos) {
+            BlockKind::Stone => erosion::weather(&mut tick, pos),
+            BlockKind::Mud => erosion::dry(&mut tick, pos),
+            BlockKind::Leaves => plant::shed(&mut tick, pos),
+            _ => (),
+        }
+    }
+
+    tick.changes
+}
+
+/// steam floats up through air until it reaches the clouds and joins them.
+/// If it's stopped before then it condenses to water, unless it's too hot to
+fn rise(tick: &mut Tick, pos: Position) {
+    let (_, y, _) = position::to_xyz(tick.dims, pos);
+    if y as u16 >= weather::cloud_layer(tick.dims) {
+        tick.set(pos, BlockKind::Air);
+        tick.changes.clouds.push(position::chunk(tick.dims, pos));
+        return;
+    }
+
+    let above = position::checked_neighbor(tick.dims, pos, Direction::Up);
+    if let Some(above) = above.filter(|&above| tick.kind(above) == BlockKind::Air) {
+        tick.move_block(pos, above);
+    } else if tick.temperature(pos) < temperature::BOILING {
+        tick.set(pos, BlockKind::Water);
+    }
+}
+
+/// sets the block alight outside of a tick, returning the change if it caught
+pub fn ignite(world: &mut World, states: &mut World, pos: Position) -> Option<BlockUpdate> {
+    let earlier = HashSet::new();
+    let mut tick = Tick::new(world, states, &earlier, position::chunk(world.dims, pos));
+    fire::ignite(&mut tick, pos);
+    let changes = tick.changes;
+    changes.apply(world, states).pop()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{
+            TYPE_AIR, TYPE_FIRE, TYPE_MUD, TYPE_SAND, TYPE_SEED, TYPE_STEAM, TYPE_STONE,
+            TYPE_WATER, TYPE_WOOD,
+        },
+        position::{self, Dimensions},
+        Reason, ScheduledTick, World,
+    };
+
+    use super::plant::SPROUT_TICKS;
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    fn place(world: &mut World, x: i16, y: i16, z: i16, data: u8) {
+        world.get_block_mut(position::from_xyz(DIMS, x, y, z)).data = data;
+    }
+
+    fn type_at(world: &World, x: i16, y: i16, z: i16) -> u8 {
+        world.get_block(position::from_xyz(DIMS, x, y, z)).data
+    }
+
+    #[test]
+    fn test_falling_and_rising() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        place(&mut world, 3, 10, 3, TYPE_SAND);
+        place(&mut world, 5, 10, 5, TYPE_STEAM);
+
+        let updates = world.simulate_chunk(&mut states, 0).updates;
+
+        assert_eq!(updates.len(), 4);
+        assert_eq!(type_at(&world, 3, 9, 3), TYPE_SAND);
+        assert_eq!(type_at(&world, 3, 10, 3), TYPE_AIR);
+        assert_eq!(type_at(&world, 5, 11, 5), TYPE_STEAM);
+        assert_eq!(type_at(&world, 5, 10, 5), TYPE_AIR);
+    }
+
+    #[test]
+    fn test_spreads_across_chunk_borders_once() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        world.set_random_ticks(0);
+        for pos in position::in_box(DIMS, (8, 0, 8), (24, 0, 8)) {
+            world.get_block_mut(pos).data = TYPE_STONE;
+        }
+        // the last column of chunk 0, which is ticked in an earlier phase than chunk 1
+        place(&mut world, 15, 1, 8, TYPE_WATER);
+
+        world.simulate(&mut states);
+        assert_eq!(type_at(&world, 14, 1, 8), TYPE_WATER);
+        assert_eq!(type_at(&world, 16, 1, 8), TYPE_WATER);
+        // the water chunk 0 put in chunk 1 waits for the next tick to spread
+        assert_eq!(type_at(&world, 13, 1, 8), TYPE_AIR);
+        assert_eq!(type_at(&world, 17, 1, 8), TYPE_AIR);
+
+        world.simulate(&mut states);
+        assert_eq!(type_at(&world, 13, 1, 8), TYPE_WATER);
+        assert_eq!(type_at(&world, 17, 1, 8), TYPE_WATER);
+    }
+
+    #[test]
+    fn test_only_active_chunks() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        assert_eq!(world.simulate(&mut states).chunks, 0);
+
+        // on the edge of chunk 0, so chunk 1 is visited too
+        place(&mut world, 15, 3, 3, TYPE_SAND);
+        let mut chunks = Vec::new();
+        for _ in 0..6 {
+            chunks.push(world.simulate(&mut states).chunks);
+        }
+
+        assert_eq!(chunks, [2, 2, 2, 2, 0, 0]);
+        assert_eq!(type_at(&world, 15, 0, 3), TYPE_SAND);
+        assert_eq!(world.ticks(), 7);
+    }
+
+    #[test]
+    fn test_scheduled_ticks() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        world.set_random_ticks(0);
+        place(&mut world, 4, 0, 4, TYPE_MUD);
+        place(&mut world, 4, 1, 4, TYPE_SEED);
+
+        // the seed schedules its sprouting, then nothing needs visiting
+        world.simulate(&mut states);
+        let seed = position::from_xyz(DIMS, 4, 1, 4);
+        let due = [ScheduledTick {
+            tick: SPROUT_TICKS,
+            pos: seed,
+            reason: Reason::Sprout,
+        }];
+        assert!(world.scheduled().eq(&due));
+        assert_eq!(world.simulate(&mut states).chunks, 0);
+
+        let mut chunks = Vec::new();
+        while world.ticks() <= SPROUT_TICKS {
+            chunks.push(world.simulate(&mut states).chunks);
+        }
+        assert_eq!(chunks.iter().sum::<usize>(), 1);
+        assert_eq!(chunks.last(), Some(&1));
+        assert_eq!(type_at(&world, 4, 1, 4), TYPE_WOOD);
+        assert_eq!(world.scheduled().count(), 0);
+    }
+
+    #[test]
+    fn test_sparse_matches_full() {
+        let grow = |sparse: bool| {
+            let mut world = World::seeded(9);
+            let mut states = World::empty();
+            for x in 0..16 {
+                for z in 0..16 {
+                    place(&mut world, x, 0, z, TYPE_MUD);
+                }
+            }
+            place(&mut world, 8, 1, 8, TYPE_SEED);
+
+            for _ in 0..250 {
+                if sparse {
+                    world.simulate(&mut states);
+                } else {
+                    world.simulate_chunk(&mut states, 0);
+                    world.end_tick();
+                }
+            }
+            world
+        };
+
+        let (sparse, full) = (grow(true), grow(false));
+        for pos in position::from_ccb(DIMS, 0, 0, 0)..=position::from_ccb(DIMS, 0, 255, 255) {
+            assert_eq!(sparse.get_block(pos).data, full.get_block(pos).data);
+        }
+    }
+
+    #[test]
+    fn test_threads_match_single_thread() {
+        let burn =
+            |threads: usize| {
+                let mut world = World::seeded(4);
+                let mut states = World::empty();
+                // a wood floor spanning four chunks, burning from one corner and
+                // flooding from another
+                for x in 8..40 {
+                    for z in 8..40 {
+                        place(&mut world, x, 0, z, TYPE_WOOD);
+                    }
+                }
+                place(&mut world, 8, 1, 8, TYPE_FIRE);
+                place(&mut world, 39, 1, 39, TYPE_WATER);
+
+                let mut updates = Vec::new();
+                for _ in 0..40 {
+                    let report = world.simulate_threaded(&mut states, threads);
+                    updates.extend(report.updates.iter().map(|update| {
+                        (update.chunk, update.column, update.block, update.new_data)
+                    }));
+                }
+                (world, states, updates)
+            };
+
+        let (world, states, updates) = burn(1);
+        let (threaded, threaded_states, threaded_updates) = burn(4);
+        assert_eq!(updates, threaded_updates);
+        assert!(world.blocks == threaded.blocks);
+        assert!(states.blocks == threaded_states.blocks);
+    }
+
+    #[test]
+    fn test_small_world() {
+        let dims = Dimensions::new(32, 16, 8).unwrap();
+        let mut world = World::with_dimensions(dims, 1);
+        let mut states = World::with_dimensions(dims, 0);
+        // in the corner of the last chunk, so it wakes chunks across both seams
+        world
+            .get_block_mut(position::from_xyz(dims, 31, 15, 31))
+            .data = TYPE_SAND;
+
+        let report = world.simulate(&mut states);
+        assert_eq!(report.chunks, 3);
+        assert_eq!(report.updates[0].chunk, 15);
+        for _ in 0..20 {
+            world.simulate(&mut states);
+        }
+
+        let landed = position::from_xyz(dims, 31, 0, 31);
+        assert_eq!(world.get_block(landed).data, TYPE_SAND);
+    }
+}
@@ -0,0 +1,219 @@
+//! plant life
+//!
+//! dirt soaks up water next to it and turns to mud. Seeds resting on mud
+//! sprout into a trunk of wood that grows upward and tops itself with leaves,
+//! and those leaves drop seeds of their own. Growth stages are kept in the
+//! states world, sprouting waits on a scheduled tick and dropping seeds happens
+//! on random ticks
+
+use rand::Rng;
+
+use crate::world::{
+    block::{BlockKind, Direction},
+    position::{self, Position},
+    schedule::Reason,
+};
+
+use super::{gravity, Tick};
+
+/// how many ticks dirt has to spend touching water before it turns to mud
+pub const SOAK_TICKS: u8 = 30;
+/// how many ticks a seed has to rest on mud before it sprouts
+pub const SPROUT_TICKS: u64 = 50;
+/// the chance leaves drop a seed when randomly ticked
+const SEED_CHANCE: f64 = 0.5;
+/// the chance each tick that the top of a growing trunk grows
+const GROW_CHANCE: f64 = 0.2;
+/// shortest and tallest trunks a seed can sprout into
+const TRUNK_HEIGHTS: (u8, u8) = (3, 5);
+
+/// growth stage of wood that has finished growing. Wood starts out fully grown
+pub const GROWN: u8 = 0;
+/// growth stage of the top of a trunk that only has its leaves left to grow
+const CROWN: u8 = 1;
+
+/// dirt touching water counts up until it turns to mud. It dries out again once it isn't
+pub fn soak(tick: &mut Tick, pos: Position) {
+    if !position::neighbors(tick.dims, pos).any(|side| tick.kind(side) == BlockKind::Water) {
+        tick.set_state(pos, 0);
+        return;
+    }
+
+    let soaked = tick.get_state(pos) + 1;
+    if soaked >= SOAK_TICKS {
+        tick.set(pos, BlockKind::Mud);
+    } else {
+        tick.set_state(pos, soaked);
+    }
+}
+
+/// seeds fall until they land. One that lands on mud schedules its sprouting
+pub fn germinate(tick: &mut Tick, pos: Position) {
+    gravity::fall(tick, pos);
+    if tick.kind(pos) == BlockKind::Seed && on_mud(tick, pos) {
+        tick.schedule(pos, SPROUT_TICKS, Reason::Sprout);
+    }
+}
+
+/// a seed still resting on mud once its time is up sprouts into the bottom of a trunk
+pub fn sprout(tick: &mut Tick, pos: Position) {
+    if tick.kind(pos) != BlockKind::Seed || !on_mud(tick, pos) {
+        return;
+    }
+
+    let height = tick.rng.gen_range(TRUNK_HEIGHTS.0..=TRUNK_HEIGHTS.1);
+    tick.set(pos, BlockKind::Wood);
+    // each block of trunk is one stage, counting down to the crown
+    tick.set_state(pos, height);
+}
+
+fn on_mud(tick: &Tick, pos: Position) -> bool {
+    let below = position::checked_neighbor(tick.dims, pos, Direction::Down);
+    below.is_some_and(|below| tick.kind(below) == BlockKind::Mud)
+}
+
+/// the top of a growing trunk sometimes grows: either another block of trunk
+/// above it, or a crown of leaves once the trunk is tall enough
+pub fn grow(tick: &mut Tick, pos: Position) {
+    let stage = tick.get_state(pos);
+    if stage == GROWN {
+        return;
+    }
+    if !tick.rng.gen_bool(GROW_CHANCE) {
+        tick.wake(pos);
+        return;
+    }
+
+    let above = position::checked_neighbor(tick.dims, pos, Direction::Up)
+        .filter(|&above| tick.kind(above) == BlockKind::Air);
+    match above {
+        Some(above) if stage != CROWN => {
+            tick.set(above, BlockKind::Wood);
+            tick.set_state(above, stage - 1);
+        }
+        // either tall enough, or blocked so this is as tall as it gets
+        _ => crown(tick, pos),
+    }
+    tick.set_state(pos, GROWN);
+}
+
+/// fills the air around and above the top of a trunk with leaves
+fn crown(tick: &mut Tick, top: Position) {
+    let (x, y, z) = position::to_xyz(tick.dims, top);
+    for leaf in position::in_box(tick.dims, (x - 1, y, z - 1), (x + 1, y + 1, z + 1)) {
+        if tick.kind(leaf) == BlockKind::Air {
+            tick.set(leaf, BlockKind::Leaves);
+        }
+    }
+}
+
+/// leaves may drop a seed into the air below them
+pub fn shed(tick: &mut Tick, pos: Position) {
+    let below = position::checked_neighbor(tick.dims, pos, Direction::Down);
+    if let Some(below) = below.filter(|&below| tick.kind(below) == BlockKind::Air) {
+        if tick.rng.gen_bool(SEED_CHANCE) {
+            tick.set(below, BlockKind::Seed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{TYPE_DIRT, TYPE_LEAVES, TYPE_MUD, TYPE_SEED, TYPE_STONE, TYPE_WATER, TYPE_WOOD},
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::SOAK_TICKS;
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    /// a mud floor filling chunk 0 with a single seed in the middle
+    fn seeded_field(seed: u64) -> (World, World) {
+        let mut world = World::seeded(seed);
+        for x in 0..16 {
+            for z in 0..16 {
+                world.get_block_mut(position::from_xyz(DIMS, x, 0, z)).data = TYPE_MUD;
+            }
+        }
+        world.get_block_mut(position::from_xyz(DIMS, 8, 1, 8)).data = TYPE_SEED;
+        (world, World::empty())
+    }
+
+    /// how many wood, leaves and seed blocks there are in chunk 0
+    fn census(world: &World) -> [usize; 3] {
+        let mut counts = [0; 3];
+        for pos in position::from_ccb(DIMS, 0, 0, 0)..=position::from_ccb(DIMS, 0, 255, 255) {
+            match world.get_block(pos).data {
+                TYPE_WOOD => counts[0] += 1,
+                TYPE_LEAVES => counts[1] += 1,
+                TYPE_SEED => counts[2] += 1,
+                _ => (),
+            }
+        }
+        counts
+    }
+
+    fn grow_forest(seed: u64, ticks: usize) -> World {
+        let (mut world, mut states) = seeded_field(seed);
+        for _ in 0..ticks {
+            world.simulate_chunk(&mut states, 0);
+            world.end_tick();
+        }
+        world
+    }
+
+    #[test]
+    fn test_seed_grows_into_tree() {
+        let world = grow_forest(3, 150);
+        let [_, leaves, _] = census(&world);
+        // leaves may have dropped seeds that sprouted too, so only count the first trunk
+        let trunk = (1..10)
+            .filter(|&y| world.get_block(position::from_xyz(DIMS, 8, y, 8)).data == TYPE_WOOD)
+            .count();
+
+        assert!((3..=5).contains(&trunk));
+        assert!(leaves >= 9);
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 8, 1, 8)).data,
+            TYPE_WOOD
+        );
+    }
+
+    #[test]
+    fn test_forest_growth_is_seeded() {
+        let first = grow_forest(11, 400);
+        let second = grow_forest(11, 400);
+
+        assert_eq!(census(&first), census(&second));
+        for pos in position::from_ccb(DIMS, 0, 0, 0)..=position::from_ccb(DIMS, 0, 255, 255) {
+            assert_eq!(first.get_block(pos).data, second.get_block(pos).data);
+        }
+        // more wood than the tallest single tree, so the first tree's seeds took root
+        assert!(census(&first)[0] > 5);
+    }
+
+    #[test]
+    fn test_wet_dirt_turns_to_mud() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        world.get_block_mut(position::from_xyz(DIMS, 4, 0, 4)).data = TYPE_DIRT;
+        world.get_block_mut(position::from_xyz(DIMS, 5, 0, 4)).data = TYPE_STONE;
+        world.get_block_mut(position::from_xyz(DIMS, 5, 1, 4)).data = TYPE_WATER;
+        world.get_block_mut(position::from_xyz(DIMS, 6, 0, 4)).data = TYPE_DIRT;
+
+        for _ in 0..=SOAK_TICKS {
+            world.simulate_chunk(&mut states, 0);
+        }
+
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 4, 0, 4)).data,
+            TYPE_MUD
+        );
+        assert_eq!(
+            world.get_block(position::from_xyz(DIMS, 6, 0, 4)).data,
+            TYPE_MUD
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! water flow
+//!
+//! water tracks its level in the states world. Sources sit at level 0 and
+//! stay put. Water flowing sideways is one level higher than the water
+//! feeding it, and dries up once nothing feeds it anymore. Water that gets too
+//! hot boils away into steam
+
+use crate::world::{
+    block::{BlockKind, Direction},
+    position::{self, Position},
+    temperature::BOILING,
+};
+
+use super::Tick;
+
+/// level of water that never moves or dries up. New water starts as a source
+pub const SOURCE: u8 = 0;
+/// flowing water at this level doesn't spread any further
+pub const MAX_LEVEL: u8 = 7;
+/// level of water falling straight down
+pub const FALLING: u8 = 8;
+
+/// water falls if it can, otherwise it spreads sideways
+pub fn flow(tick: &mut Tick, pos: Position) {
+    if tick.temperature(pos) >= BOILING {
+        tick.set(pos, BlockKind::Steam);
+        return;
+    }
+
+    let mut level = tick.get_state(pos);
+
+    if level != SOURCE {
+        match fed_level(tick, pos) {
+            Some(fed) => level = fed,
+            None => {
+                tick.set(pos, BlockKind::Air);
+                return;
+            }
+        }
+        tick.set_state(pos, level);
+    }
+
+    if let Some(below) = position::checked_neighbor(tick.dims, pos, Direction::Down) {
+        match tick.kind(below) {
+            BlockKind::Air => {
+                tick.set(below, BlockKind::Water);
+                tick.set_state(below, FALLING);
+                return;
+            }
+            // joins the water below rather than spreading over it
+            BlockKind::Water => return,
+            _ => (),
+        }
+    }
+
+    let next = spread_level(level);
+    if next > MAX_LEVEL {
+        return;
+    }
+
+    for side in position::horizontal_neighbors(tick.dims, pos) {
+        if tick.kind(side) == BlockKind::Air {
+            tick.set(side, BlockKind::Water);
+            tick.set_state(side, next);
+        }
+    }
+}
+
+/// the level of water flowing out of water at `level`
+fn spread_level(level: u8) -> u8 {
+    match level {
+        FALLING => 1,
+        level => level + 1,
+    }
+}
+
+/// the level flowing water should be at given the water around it,
+/// or `None` if nothing is feeding it
+fn fed_level(tick: &Tick, pos: Position) -> Option<u8> {
+    let above = position::checked_neighbor(tick.dims, pos, Direction::Up);
+    if above.is_some_and(|above| tick.kind(above) == BlockKind::Water) {
+        return Some(FALLING);
+    }
+
+    position::horizontal_neighbors(tick.dims, pos)
+        .filter(|&side| tick.kind(side) == BlockKind::Water)
+        .map(|side| spread_level(tick.get_state(side)))
+        .min()
+        .filter(|&level| level <= MAX_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{Direction, TYPE_AIR, TYPE_STONE, TYPE_WATER},
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::{FALLING, MAX_LEVEL};
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    #[test]
+    fn test_source_falls_then_spreads() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        let source = position::from_xyz(DIMS, 8, 2, 8);
+        world.get_block_mut(source).data = TYPE_WATER;
+        for x in 0..16 {
+            for z in 0..16 {
+                world.get_block_mut(position::from_xyz(DIMS, x, 0, z)).data = TYPE_STONE;
+            }
+        }
+
+        world.simulate_chunk(&mut states, 0);
+        assert_eq!(world.get_block(source - 1).data, TYPE_WATER);
+        assert_eq!(states.get_block(source - 1).data, FALLING);
+
+        world.simulate_chunk(&mut states, 0);
+        let side = position::from_xyz(DIMS, 9, 1, 8);
+        assert_eq!(world.get_block(side).data, TYPE_WATER);
+        assert_eq!(states.get_block(side).data, 1);
+
+        for _ in 0..10 {
+            world.simulate_chunk(&mut states, 0);
+        }
+        // the source never moves, and flow stops at the max level
+        assert_eq!(world.get_block(source).data, TYPE_WATER);
+        let last = position::from_xyz(DIMS, 8 - MAX_LEVEL as i16, 1, 8);
+        assert_eq!(states.get_block(last).data, MAX_LEVEL);
+        let past = position::neighbor(DIMS, last, Direction::West);
+        assert_eq!(world.get_block(past).data, TYPE_AIR);
+    }
+
+    #[test]
+    fn test_dries_up_without_source() {
+        let mut world = World::empty();
+        let mut states = World::empty();
+        let source = position::from_xyz(DIMS, 8, 0, 8);
+        world.get_block_mut(source).data = TYPE_WATER;
+
+        for _ in 0..4 {
+            world.simulate_chunk(&mut states, 0);
+        }
+        world.get_block_mut(source).data = TYPE_AIR;
+        for _ in 0..20 {
+            world.simulate_chunk(&mut states, 0);
+        }
+
+        for x in 0..16 {
+            assert_eq!(
+                world.get_block(position::from_xyz(DIMS, x, 0, 8)).data,
+                TYPE_AIR
+            );
+        }
+    }
+}
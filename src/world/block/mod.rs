@@ -1,4 +1,10 @@
-use super::position::Position;
+use std::{error::Error, fmt};
+
+use super::position::{self, Dimensions, Position};
+
+pub use properties::BlockProperties;
+
+mod properties;
 
 // blocks
 // NOTE: these properties are not comprehensive; see block behaviors for more details
@@ -32,8 +38,8 @@ pub const TYPE_DOOR: u8 = 12;
 pub const TYPE_CANNON: u8 = 13;
 /// destroys wood, leaves, steam, seeds, spreads rapidly
 pub const TYPE_FIRE: u8 = 14;
-/// placeholder
-pub const TYPE_TBD: u8 = 15;
+/// spreads through the world and destroys whatever it reaches, slowed by tougher materials
+pub const TYPE_ILL: u8 = 15;
 
 // these directions are combined with the above block kinds to create the data
 // for the block type
@@ -50,7 +56,133 @@ pub const DIR_S: u8 = 80;
 /// west
 pub const DIR_W: u8 = 96;
 
-#[derive(Default, Clone, Copy)]
+/// the bits of block data holding its type
+const TYPE_MASK: u8 = 0b0000_1111;
+/// the bits of block data holding its rotation
+const DIR_MASK: u8 = 0b1111_0000;
+
+/// the type of a block, matching the `TYPE_*` constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum BlockKind {
+    Air = TYPE_AIR,
+    Water = TYPE_WATER,
+    Steam = TYPE_STEAM,
+    Dirt = TYPE_DIRT,
+    Mud = TYPE_MUD,
+    Sand = TYPE_SAND,
+    Lava = TYPE_LAVA,
+    Stone = TYPE_STONE,
+    Wood = TYPE_WOOD,
+    Leaves = TYPE_LEAVES,
+    Seed = TYPE_SEED,
+    Shrine = TYPE_SHRINE,
+    Door = TYPE_DOOR,
+    Cannon = TYPE_CANNON,
+    Fire = TYPE_FIRE,
+    Ill = TYPE_ILL,
+}
+
+impl BlockKind {
+    /// every kind, indexed by its type number
+    pub const ALL: [BlockKind; 16] = [
+        BlockKind::Air,
+        BlockKind::Water,
+        BlockKind::Steam,
+        BlockKind::Dirt,
+        BlockKind::Mud,
+        BlockKind::Sand,
+        BlockKind::Lava,
+        BlockKind::Stone,
+        BlockKind::Wood,
+        BlockKind::Leaves,
+        BlockKind::Seed,
+        BlockKind::Shrine,
+        BlockKind::Door,
+        BlockKind::Cannon,
+        BlockKind::Fire,
+        BlockKind::Ill,
+    ];
+}
+
+impl TryFrom<u8> for BlockKind {
+    type Error = InvalidBlockData;
+
+    /// only accepts type numbers, without any rotation
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        BlockKind::ALL
+            .get(value as usize)
+            .copied()
+            .ok_or(InvalidBlockData(value))
+    }
+}
+
+/// the rotation of a block, matching the `DIR_*` constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Direction {
+    Up = DIR_U,
+    Down = DIR_D,
+    North = DIR_N,
+    East = DIR_E,
+    South = DIR_S,
+    West = DIR_W,
+}
+
+impl Direction {
+    /// every direction, in the same order as the `DIR_*` constants
+    pub const ALL: [Direction; 6] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// the (x, y, z) offset to the next block over in this direction.
+    /// North is toward -z and east is toward +x
+    pub fn offset(self) -> (i16, i16, i16) {
+        match self {
+            Direction::Up => (0, 1, 0),
+            Direction::Down => (0, -1, 0),
+            Direction::North => (0, 0, -1),
+            Direction::East => (1, 0, 0),
+            Direction::South => (0, 0, 1),
+            Direction::West => (-1, 0, 0),
+        }
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+impl TryFrom<u8> for Direction {
+    type Error = InvalidBlockData;
+
+    /// only accepts the rotation bits, without any type
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            DIR_U => Ok(Direction::Up),
+            DIR_D => Ok(Direction::Down),
+            DIR_N => Ok(Direction::North),
+            DIR_E => Ok(Direction::East),
+            DIR_S => Ok(Direction::South),
+            DIR_W => Ok(Direction::West),
+            _ => Err(InvalidBlockData(value)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Block {
     /// the type and rotation of the block
     /// the four least significant bits (2^0 through 2^3) represent the type of block
@@ -59,14 +191,101 @@ pub struct Block {
 }
 
 impl Block {
+    /// takes the data as is. Use `Block::try_from` to check it first
     pub fn new(data: u8) -> Block {
         Block { data }
     }
+
+    pub fn kind(&self) -> BlockKind {
+        BlockKind::ALL[(self.data & TYPE_MASK) as usize]
+    }
+
+    /// `None` if the block has no rotation
+    pub fn direction(&self) -> Option<Direction> {
+        Direction::try_from(self.data & DIR_MASK).ok()
+    }
+
+    /// the same kind of block, rotated to face `direction`
+    pub fn with_direction(self, direction: Direction) -> Block {
+        Block {
+            data: (self.data & TYPE_MASK) | direction as u8,
+        }
+    }
+}
+
+impl From<BlockKind> for Block {
+    fn from(kind: BlockKind) -> Self {
+        Block { data: kind as u8 }
+    }
+}
+
+impl TryFrom<u8> for Block {
+    type Error = InvalidBlockData;
+
+    /// accepts any type, but rejects rotations past `DIR_W`
+    fn try_from(data: u8) -> Result<Self, Self::Error> {
+        if data & DIR_MASK > DIR_W {
+            Err(InvalidBlockData(data))
+        } else {
+            Ok(Block { data })
+        }
+    }
+}
+
+/// data that doesn't represent a block, kind or direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidBlockData(pub u8);
+
+impl fmt::Display for InvalidBlockData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid block data {:#010b}", self.0)
+    }
 }
 
+impl Error for InvalidBlockData {}
+
+#[derive(Clone, Copy)]
 pub struct BlockUpdate {
     pub chunk: u8,
     pub column: u8,
     pub block: u8,
     pub new_data: u8,
-}
\ No newline at end of file
+}
+
+impl BlockUpdate {
+    pub fn new(dims: Dimensions, pos: Position, new_data: u8) -> BlockUpdate {
+        BlockUpdate {
+            chunk: position::chunk(dims, pos),
+            column: position::column(dims, pos),
+            block: position::block(dims, pos),
+            new_data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Block, BlockKind, Direction, DIR_E, DIR_W, TYPE_CANNON, TYPE_ILL};
+
+    #[test]
+    fn test_kind_and_direction() {
+        let block = Block::try_from(TYPE_CANNON | DIR_E).unwrap();
+        assert_eq!(block.kind(), BlockKind::Cannon);
+        assert_eq!(block.direction(), Some(Direction::East));
+
+        let turned = block.with_direction(Direction::North);
+        assert_eq!(turned.kind(), BlockKind::Cannon);
+        assert_eq!(turned.direction(), Some(Direction::North));
+
+        assert_eq!(Block::from(BlockKind::Stone).direction(), None);
+    }
+
+    #[test]
+    fn test_rejects_invalid_data() {
+        assert!(Block::try_from(TYPE_ILL | DIR_W).is_ok());
+        assert!(Block::try_from(TYPE_ILL | (DIR_W + 16)).is_err());
+        assert!(Block::try_from(0xFF).is_err());
+        assert!(BlockKind::try_from(TYPE_ILL + 1).is_err());
+        assert!(Direction::try_from(DIR_E | TYPE_CANNON).is_err());
+    }
+}
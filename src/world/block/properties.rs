@@ -0,0 +1,218 @@
+use super::BlockKind;
+
+/// how a kind of block behaves, for anything that needs to ask
+pub struct BlockProperties {
+    /// how heavy the block is. Heavier blocks sink through lighter fluids
+    pub density: u8,
+    /// whether fire spreads to it
+    pub flammable: bool,
+    /// whether it blocks movement and holds up blocks resting on it
+    pub solid: bool,
+    /// whether it flows
+    pub fluid: bool,
+    /// how much light it gives off, from 0 (none) to 15
+    pub light: u8,
+    /// how much it resists being broken or worn down
+    pub hardness: u8,
+    /// how hot it keeps the space around it, or 0 if it doesn't give off heat
+    pub heat: u8,
+}
+
+impl BlockKind {
+    pub fn properties(self) -> &'static BlockProperties {
+        &PROPERTIES[self as usize]
+    }
+}
+
+/// properties of every kind, indexed by type number
+const PROPERTIES: [BlockProperties; 16] = [
+    // air
+    BlockProperties {
+        density: 2,
+        flammable: false,
+        solid: false,
+        fluid: false,
+        light: 0,
+        hardness: 0,
+        heat: 0,
+    },
+    // water
+    BlockProperties {
+        density: 10,
+        flammable: false,
+        solid: false,
+        fluid: true,
+        light: 0,
+        hardness: 0,
+        heat: 0,
+    },
+    // steam
+    BlockProperties {
+        density: 1,
+        flammable: false,
+        solid: false,
+        fluid: true,
+        light: 0,
+        hardness: 0,
+        heat: 0,
+    },
+    // dirt
+    BlockProperties {
+        density: 15,
+        flammable: false,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 2,
+        heat: 0,
+    },
+    // mud
+    BlockProperties {
+        density: 14,
+        flammable: false,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 1,
+        heat: 0,
+    },
+    // sand
+    BlockProperties {
+        density: 16,
+        flammable: false,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 1,
+        heat: 0,
+    },
+    // lava
+    BlockProperties {
+        density: 30,
+        flammable: false,
+        solid: false,
+        fluid: true,
+        light: 15,
+        hardness: 0,
+        heat: 250,
+    },
+    // stone
+    BlockProperties {
+        density: 25,
+        flammable: false,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 6,
+        heat: 0,
+    },
+    // wood
+    BlockProperties {
+        density: 7,
+        flammable: true,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 4,
+        heat: 0,
+    },
+    // leaves
+    BlockProperties {
+        density: 3,
+        flammable: true,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 1,
+        heat: 0,
+    },
+    // seed
+    BlockProperties {
+        density: 12,
+        flammable: true,
+        solid: false,
+        fluid: false,
+        light: 0,
+        hardness: 1,
+        heat: 0,
+    },
+    // shrine
+    BlockProperties {
+        density: 40,
+        flammable: false,
+        solid: true,
+        fluid: false,
+        light: 7,
+        hardness: 255,
+        heat: 0,
+    },
+    // door
+    BlockProperties {
+        density: 8,
+        flammable: false,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 5,
+        heat: 0,
+    },
+    // cannon
+    BlockProperties {
+        density: 40,
+        flammable: false,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 8,
+        heat: 0,
+    },
+    // fire
+    BlockProperties {
+        density: 1,
+        flammable: false,
+        solid: false,
+        fluid: false,
+        light: 14,
+        hardness: 0,
+        heat: 180,
+    },
+    // ill
+    BlockProperties {
+        density: 10,
+        flammable: false,
+        solid: true,
+        fluid: false,
+        light: 0,
+        hardness: 3,
+        heat: 0,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::world::block::BlockKind;
+
+    #[test]
+    fn test_table_order() {
+        let fluids = BlockKind::ALL
+            .into_iter()
+            .filter(|kind| kind.properties().fluid)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fluids,
+            [BlockKind::Water, BlockKind::Steam, BlockKind::Lava]
+        );
+
+        let flammable = BlockKind::ALL
+            .into_iter()
+            .filter(|kind| kind.properties().flammable)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flammable,
+            [BlockKind::Wood, BlockKind::Leaves, BlockKind::Seed]
+        );
+
+        assert_eq!(BlockKind::Lava.properties().light, 15);
+        assert_eq!(BlockKind::Stone.properties().hardness, 6);
+    }
+}
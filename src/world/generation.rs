@@ -1,6 +1,444 @@
-use super::World;
+use std::{error::Error, fmt};
 
-pub fn generate() -> World {
-    // todo
-    World::empty()
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{
+    block::{
+        TYPE_AIR, TYPE_DIRT, TYPE_LAVA, TYPE_LEAVES, TYPE_MUD, TYPE_SAND, TYPE_STONE, TYPE_WATER,
+        TYPE_WOOD,
+    },
+    position::{self, Dimensions},
+    World,
+};
+
+/// parameters for generating a world.
+/// The same config always generates exactly the same world
+#[derive(Debug, Clone)]
+pub struct GenerationConfig {
+    /// seeds the terrain, and the world's simulation after it
+    pub seed: u64,
+    /// the size of the world. The heights below should fit inside it
+    pub dimensions: Dimensions,
+    /// water fills every column up to this height
+    pub sea_level: u8,
+    /// average height of the ground
+    pub base_height: u8,
+    /// how far the ground can rise above or sink below `base_height`
+    pub amplitude: u8,
+    /// width in blocks of the largest hills. Should divide the world's width so the terrain wraps seamlessly
+    pub wavelength: usize,
+    /// how many layers of finer and finer detail are added to the hills
+    pub octaves: u8,
+    /// how deep the dirt, mud or sand over the stone goes
+    pub soil_depth: u8,
+    /// ground this close to sea level is beach
+    pub beach_height: u8,
+    /// how many pools of lava to put on land
+    pub hotspots: u32,
+    /// radius of each pool of lava
+    pub hotspot_radius: u8,
+    /// the chance that a tree grows on any given column of dirt
+    pub tree_chance: f64,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        GenerationConfig {
+            seed: 0,
+            dimensions: Dimensions::DEFAULT,
+            sea_level: 64,
+            base_height: 68,
+            amplitude: 24,
+            wavelength: 64,
+            octaves: 4,
+            soil_depth: 3,
+            beach_height: 2,
+            hotspots: 6,
+            hotspot_radius: 3,
+            tree_chance: 0.01,
+        }
+    }
+}
+
+impl GenerationConfig {
+    /// the sea has to be below the top of the world, and the tree chance between 0 and 1
+    pub fn validate(&self) -> Result<(), InvalidGenerationConfig> {
+        let valid = (self.sea_level as u16) < self.dimensions.height()
+            && (0.0..=1.0).contains(&self.tree_chance);
+        if valid {
+            Ok(())
+        } else {
+            Err(InvalidGenerationConfig(self.clone()))
+        }
+    }
+}
+
+/// a config that breaks one of the requirements in [`GenerationConfig::validate`]
+#[derive(Debug, Clone)]
+pub struct InvalidGenerationConfig(pub GenerationConfig);
+
+impl fmt::Display for InvalidGenerationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid generation config with sea level {} in a world {} high and a tree chance of {}",
+            self.0.sea_level,
+            self.0.dimensions.height(),
+            self.0.tree_chance
+        )
+    }
+}
+
+impl Error for InvalidGenerationConfig {}
+
+pub fn generate(config: &GenerationConfig) -> Result<World, InvalidGenerationConfig> {
+    config.validate()?;
+    let dims = config.dimensions;
+    let width = dims.width() as i16;
+    let mut world = World::with_dimensions(dims, config.seed);
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+
+    let heights = heightmap(config);
+    let height = |x: i16, z: i16| {
+        heights[x.rem_euclid(width) as usize * width as usize + z.rem_euclid(width) as usize]
+    };
+
+    for x in 0..width {
+        for z in 0..width {
+            fill_column(&mut world, config, x, z, height(x, z));
+        }
+    }
+
+    for _ in 0..config.hotspots {
+        let (x, z) = (rng.gen_range(0..width), rng.gen_range(0..width));
+        // lava under the sea would just turn to stone
+        if height(x, z) > config.sea_level as i16 {
+            hotspot(&mut world, config, x, height(x, z), z);
+        }
+    }
+
+    for x in 0..width {
+        for z in 0..width {
+            let y = height(x, z);
+            let dirt = world.get_block(position::from_xyz(dims, x, y, z)).data == TYPE_DIRT;
+            if rng.gen_bool(config.tree_chance) && dirt {
+                let trunk = rng.gen_range(3..=5);
+                tree(&mut world, x, y + 1, z, trunk);
+            }
+        }
+    }
+
+    Ok(world)
+}
+
+/// the height of the ground in every column, indexed by x * width + z
+fn heightmap(config: &GenerationConfig) -> Vec<i16> {
+    let width = config.dimensions.width() as usize;
+    let top = config.dimensions.height() as f64 - 2.0;
+    let mut heights = Vec::with_capacity(width * width);
+    for x in 0..width {
+        for z in 0..width {
+            let noise = fractal_noise(config, x, z) * 2.0 - 1.0;
+            let height = config.base_height as f64 + noise * config.amplitude as f64;
+            heights.push(height.round().clamp(1.0, top.max(1.0)) as i16);
+        }
+    }
+    heights
+}
+
+/// layered value noise between 0 and 1. Each octave has half the wavelength
+/// and half the weight of the one before it
+fn fractal_noise(config: &GenerationConfig, x: usize, z: usize) -> f64 {
+    let mut total = 0.0;
+    let mut weight = 1.0;
+    let mut weights = 0.0;
+    let mut wavelength = config.wavelength.max(1);
+
+    for octave in 0..config.octaves {
+        let seed = config.seed.wrapping_add(octave as u64);
+        let noise = value_noise(seed, config.dimensions.width() as usize, x, z, wavelength);
+        total += noise * weight;
+        weights += weight;
+        weight /= 2.0;
+        wavelength = (wavelength / 2).max(1);
+    }
+
+    if weights == 0.0 {
+        0.5
+    } else {
+        total / weights
+    }
+}
+
+/// smoothly interpolated random values on a lattice `wavelength` blocks apart.
+/// The lattice wraps around with the world
+fn value_noise(seed: u64, width: usize, x: usize, z: usize, wavelength: usize) -> f64 {
+    let cells = (width / wavelength).max(1);
+    let (cx, cz) = (x / wavelength, z / wavelength);
+    let (fx, fz) = (
+        smoothstep((x % wavelength) as f64 / wavelength as f64),
+        smoothstep((z % wavelength) as f64 / wavelength as f64),
+    );
+    let corner = |dx: usize, dz: usize| lattice(seed, (cx + dx) % cells, (cz + dz) % cells);
+
+    let north = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * fx;
+    let south = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * fx;
+    north + (south - north) * fz
+}
+
+fn smoothstep(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// a random value between 0 and 1 fixed to a lattice point
+fn lattice(seed: u64, x: usize, z: usize) -> f64 {
+    // splitmix64 finalizer
+    let mut h = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^= h >> 31;
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// stone, topped with soil and filled with water up to sea level.
+/// The soil is mud under the water, sand along the shore and dirt everywhere else
+fn fill_column(world: &mut World, config: &GenerationConfig, x: i16, z: i16, height: i16) {
+    let sea_level = config.sea_level as i16;
+    let soil = if height < sea_level - config.beach_height as i16 {
+        TYPE_MUD
+    } else if height <= sea_level + config.beach_height as i16 {
+        TYPE_SAND
+    } else {
+        TYPE_DIRT
+    };
+
+    let dims = world.dimensions();
+    for y in 0..=height.max(sea_level) {
+        let data = if y <= height - config.soil_depth as i16 {
+            TYPE_STONE
+        } else if y <= height {
+            soil
+        } else {
+            TYPE_WATER
+        };
+        world.get_block_mut(position::from_xyz(dims, x, y, z)).data = data;
+    }
+}
+
+/// a bowl of lava sunk into the ground
+fn hotspot(world: &mut World, config: &GenerationConfig, x: i16, height: i16, z: i16) {
+    let dims = world.dimensions();
+    let r = config.hotspot_radius as i32;
+    let center = position::from_xyz(dims, x, height, z);
+    for pos in position::around(dims, center, r as i16) {
+        let below = position::to_xyz(dims, pos).1 <= height;
+        if below && position::distance_squared(dims, center, pos) <= r * r {
+            world.get_block_mut(pos).data = TYPE_LAVA;
+        }
+    }
+}
+
+/// a grown tree, with its trunk starting at (x, y, z)
+fn tree(world: &mut World, x: i16, y: i16, z: i16, trunk: i16) {
+    let dims = world.dimensions();
+    let top = y + trunk - 1;
+    for pos in position::in_box(dims, (x - 1, top, z - 1), (x + 1, top + 1, z + 1)) {
+        let leaf = world.get_block_mut(pos);
+        if leaf.data == TYPE_AIR {
+            leaf.data = TYPE_LEAVES;
+        }
+    }
+    for dy in y..=top {
+        world.get_block_mut(position::from_xyz(dims, x, dy, z)).data = TYPE_WOOD;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{TYPE_AIR, TYPE_DIRT, TYPE_LAVA, TYPE_MUD, TYPE_SAND, TYPE_STONE, TYPE_WOOD},
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::{generate, heightmap, GenerationConfig};
+
+    /// a world small enough to generate quickly, without any lava or trees
+    fn small() -> GenerationConfig {
+        GenerationConfig {
+            dimensions: Dimensions::new(64, 64, 16).unwrap(),
+            sea_level: 24,
+            base_height: 28,
+            amplitude: 8,
+            wavelength: 32,
+            hotspots: 0,
+            tree_chance: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn count(world: &World, data: u8) -> usize {
+        let dims = world.dimensions();
+        position::in_box(dims, (0, 0, 0), (63, 63, 63))
+            .filter(|&pos| world.get_block(pos).data == data)
+            .count()
+    }
+
+    #[test]
+    fn test_same_seed_same_world() {
+        let config = GenerationConfig {
+            seed: 42,
+            ..Default::default()
+        };
+        let first = generate(&config).unwrap();
+        let second = generate(&config).unwrap();
+        let other = generate(&GenerationConfig {
+            seed: 43,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(first.blocks == second.blocks);
+        assert!(first.blocks != other.blocks);
+        assert_eq!(first.seed(), 42);
+    }
+
+    #[test]
+    fn test_layers() {
+        let config = GenerationConfig::default();
+        let world = generate(&config).unwrap();
+
+        for (x, z) in [(0, 0), (100, 37), (255, 255)] {
+            assert_eq!(
+                world
+                    .get_block(position::from_xyz(world.dimensions(), x, 0, z))
+                    .data,
+                TYPE_STONE
+            );
+            assert_eq!(
+                world
+                    .get_block(position::from_xyz(world.dimensions(), x, 255, z))
+                    .data,
+                TYPE_AIR
+            );
+        }
+    }
+
+    #[test]
+    fn test_small_world() {
+        let dims = Dimensions::new(64, 64, 16).unwrap();
+        let world = generate(&GenerationConfig {
+            dimensions: dims,
+            sea_level: 24,
+            base_height: 28,
+            amplitude: 8,
+            wavelength: 32,
+            hotspots: 1,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(world.dimensions(), dims);
+        for (x, z) in [(0, 0), (63, 63)] {
+            assert_eq!(
+                world.get_block(position::from_xyz(dims, x, 0, z)).data,
+                TYPE_STONE
+            );
+            assert_eq!(
+                world.get_block(position::from_xyz(dims, x, 63, z)).data,
+                TYPE_AIR
+            );
+        }
+    }
+
+    #[test]
+    fn test_beaches() {
+        let config = small();
+        let world = generate(&config).unwrap();
+        let heights = heightmap(&config);
+        let (sea, beach) = (config.sea_level as i16, config.beach_height as i16);
+
+        for x in 0..64 {
+            for z in 0..64 {
+                let height = heights[x as usize * 64 + z as usize];
+                let soil = if height < sea - beach {
+                    TYPE_MUD
+                } else if height <= sea + beach {
+                    TYPE_SAND
+                } else {
+                    TYPE_DIRT
+                };
+                let pos = position::from_xyz(world.dimensions(), x, height, z);
+                assert_eq!(world.get_block(pos).data, soil, "{x}, {z}");
+            }
+        }
+        assert!(count(&world, TYPE_SAND) > 0);
+    }
+
+    #[test]
+    fn test_hotspots() {
+        assert_eq!(count(&generate(&small()).unwrap(), TYPE_LAVA), 0);
+        let world = generate(&GenerationConfig {
+            hotspots: 20,
+            ..small()
+        })
+        .unwrap();
+        assert!(count(&world, TYPE_LAVA) > 0);
+
+        // wider than the whole world
+        let world = generate(&GenerationConfig {
+            hotspots: 20,
+            hotspot_radius: u8::MAX,
+            ..small()
+        })
+        .unwrap();
+        assert!(count(&world, TYPE_LAVA) > 0);
+    }
+
+    #[test]
+    fn test_trees() {
+        assert_eq!(count(&generate(&small()).unwrap(), TYPE_WOOD), 0);
+        let config = GenerationConfig {
+            tree_chance: 1.0,
+            ..small()
+        };
+        let world = generate(&config).unwrap();
+        let heights = heightmap(&config);
+        assert!(count(&world, TYPE_WOOD) > 0);
+
+        // every column of dirt has a trunk on it
+        let dims = world.dimensions();
+        for x in 0..64 {
+            for z in 0..64 {
+                let height = heights[x as usize * 64 + z as usize];
+                if world.get_block(position::from_xyz(dims, x, height, z)).data == TYPE_DIRT {
+                    let trunk = position::from_xyz(dims, x, height + 1, z);
+                    assert_eq!(world.get_block(trunk).data, TYPE_WOOD);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_configs() {
+        for config in [
+            GenerationConfig {
+                tree_chance: 1.5,
+                ..small()
+            },
+            GenerationConfig {
+                tree_chance: f64::NAN,
+                ..small()
+            },
+            GenerationConfig {
+                sea_level: 64,
+                ..small()
+            },
+        ] {
+            assert!(generate(&config).is_err());
+        }
+    }
 }
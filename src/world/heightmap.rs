@@ -0,0 +1,158 @@
+//! the height of the surface in every column
+//!
+//! writes only hand out a reference, so the new block isn't known when a
+//! column changes. Instead the column remembers the highest block written
+//! since it was last settled. Everything above both that and the old surface
+//! is still air, so finding the new surface only means looking down from there
+
+use super::{block::BlockKind, storage::Storage};
+
+pub struct Heightmap {
+    /// one above the highest block that isn't air in each column, as of the last settle.
+    /// Columns are in the same order as in the world
+    tops: Vec<u16>,
+    /// one above the highest block written in each column since it was
+    /// last settled, or 0 if it hasn't been written to
+    written: Vec<u16>,
+    /// columns with anything in `written`
+    unsettled: Vec<usize>,
+}
+
+impl Heightmap {
+    /// the heights of a world of nothing but air
+    pub fn new(columns: usize) -> Heightmap {
+        Heightmap {
+            tops: vec![0; columns],
+            written: vec![0; columns],
+            unsettled: Vec::new(),
+        }
+    }
+
+    /// notes that the block `y` blocks up the column might have changed
+    pub fn write(&mut self, column: usize, y: u16) {
+        if self.written[column] == 0 {
+            self.unsettled.push(column);
+        }
+        self.written[column] = self.written[column].max(y + 1);
+    }
+
+    /// notes that any block in any column might have changed
+    pub fn write_all(&mut self, height: u16) {
+        self.unsettled = (0..self.written.len()).collect();
+        self.written.fill(height);
+    }
+
+    /// one above the highest block that isn't air in the column, or 0 if it's all air
+    pub fn top(&self, blocks: &Storage, height: u16, column: usize) -> u16 {
+        match self.written[column] {
+            0 => self.tops[column],
+            written => scan(blocks, height, column, written.max(self.tops[column])),
+        }
+    }
+
+    /// works out the surface of every column written to since the last settle
+    pub fn settle(&mut self, blocks: &Storage, height: u16) {
+        for column in self.unsettled.drain(..) {
+            let from = self.written[column].max(self.tops[column]);
+            self.tops[column] = scan(blocks, height, column, from);
+            self.written[column] = 0;
+        }
+    }
+}
+
+/// one above the highest block below `from` in the column that isn't air, or 0 if there isn't one
+fn scan(blocks: &Storage, height: u16, column: usize, from: u16) -> u16 {
+    let bottom = column * height as usize;
+    (0..from)
+        .rev()
+        .find(|&y| blocks.get(bottom + y as usize).kind() != BlockKind::Air)
+        .map_or(0, |y| y + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::world::{
+        block::{BlockKind, BlockUpdate, TYPE_AIR, TYPE_STONE},
+        position::{self, Dimensions},
+        GenerationConfig, World,
+    };
+
+    /// the surface of every column the slow way
+    fn assert_matches_scan(world: &World) {
+        let dims = world.dimensions();
+        let width = dims.width() as i16;
+        for x in 0..width {
+            for z in 0..width {
+                let expected = (0..dims.height() as i16).rev().find(|&y| {
+                    let pos = position::from_xyz(dims, x, y, z);
+                    world.get_block(pos).kind() != BlockKind::Air
+                });
+                assert_eq!(world.surface_height(x, z), expected, "({x}, {z})");
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_scan() {
+        let dims = Dimensions::new(32, 64, 16).unwrap();
+        let mut world = World::generate(&GenerationConfig {
+            dimensions: dims,
+            sea_level: 20,
+            base_height: 24,
+            amplitude: 8,
+            wavelength: 16,
+            ..Default::default()
+        })
+        .unwrap();
+        let mut states = World::with_dimensions(dims, 0);
+        assert_matches_scan(&world);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for round in 0..20 {
+            for _ in 0..50 {
+                let (x, y, z) = (
+                    rng.gen_range(0..32),
+                    rng.gen_range(0..64),
+                    rng.gen_range(0..32),
+                );
+                let pos = position::from_xyz(dims, x, y, z);
+                let data = if rng.gen_bool(0.5) {
+                    TYPE_AIR
+                } else {
+                    TYPE_STONE
+                };
+                if round % 2 == 0 {
+                    world.get_block_mut(pos).data = data;
+                } else {
+                    world.process_update(BlockUpdate::new(dims, pos, data));
+                }
+            }
+            // checked both before and after the tick settles the heights
+            assert_matches_scan(&world);
+            world.simulate(&mut states);
+            assert_matches_scan(&world);
+        }
+    }
+
+    #[test]
+    fn test_digging_out_the_top() {
+        let mut world = World::empty();
+        let dims = world.dimensions();
+        for y in 0..10 {
+            world.get_block_mut(position::from_xyz(dims, 5, y, 5)).data = TYPE_STONE;
+        }
+        world.end_tick();
+        assert_eq!(world.surface_height(5, 5), Some(9));
+        assert_eq!(world.surface_height(5 - 256, 5 + 256), Some(9));
+
+        world.get_block_mut(position::from_xyz(dims, 5, 9, 5)).data = TYPE_AIR;
+        world.get_block_mut(position::from_xyz(dims, 5, 8, 5)).data = TYPE_AIR;
+        assert_eq!(world.surface_height(5, 5), Some(7));
+        world.end_tick();
+        assert_eq!(world.surface_height(5, 5), Some(7));
+        assert_eq!(world.surface_height(6, 5), None);
+    }
+}
@@ -0,0 +1,322 @@
+//! light levels for rendering
+//!
+//! every block has two light levels from 0 to 15. Sunlight fills everything
+//! above the surface of each column at full strength, and block light starts
+//! at whatever the block gives off (see [`BlockProperties`]). Both spread out
+//! from there, losing one level per block, and solid blocks stop them
+//!
+//! after changing a block, the light around it is first taken away as far as
+//! it could have reached, then spread back in from whatever is still lit
+//!
+//! [`BlockProperties`]: super::block::BlockProperties
+
+use std::collections::VecDeque;
+
+use super::{
+    block::BlockUpdate,
+    position::{self, Dimensions, Position},
+    World,
+};
+
+/// the brightest light can be
+pub const MAX_LIGHT: u8 = 15;
+
+#[derive(Clone, Copy)]
+enum Channel {
+    Sky,
+    Block,
+}
+
+/// the light level of every block in a world
+pub struct Lighting {
+    dims: Dimensions,
+    /// sunlight in the high nibble, block light in the low one, in the same order as the world
+    levels: Vec<u8>,
+    /// the lowest block in each column that gets full sunlight, as of the last update
+    sunlit: Vec<u16>,
+}
+
+impl Lighting {
+    /// lights the whole world from scratch
+    pub fn new(world: &World) -> Lighting {
+        let dims = world.dimensions();
+        let height = dims.height();
+        let sunlit = (0..dims.columns())
+            .map(|column| world.heights.top(&world.blocks, height, column))
+            .collect::<Vec<_>>();
+        let mut lighting = Lighting {
+            dims,
+            levels: vec![0; dims.volume()],
+            sunlit,
+        };
+
+        let mut lit = VecDeque::new();
+        for column in 0..dims.columns() {
+            let bottom = column * height as usize;
+            let top = lighting.sunlit[column];
+            for y in top..height {
+                lighting.set(Channel::Sky, bottom + y as usize, MAX_LIGHT);
+            }
+
+            // sunlight only has somewhere to spread where it's beside or above
+            // a block that isn't in full sunlight
+            let beside = position::horizontal_neighbors(dims, bottom)
+                .map(|side| lighting.sunlit[side / height as usize])
+                .max()
+                .unwrap_or(0);
+            let edge = beside.max(top + 1).min(height);
+            lit.extend((top..edge).map(|y| bottom + y as usize));
+        }
+        lighting.spread(world, Channel::Sky, lit);
+
+        let lit = (0..dims.volume())
+            .filter(|&pos| lighting.source(world, Channel::Block, pos) > 0)
+            .collect::<VecDeque<_>>();
+        for &pos in &lit {
+            let source = lighting.source(world, Channel::Block, pos);
+            lighting.set(Channel::Block, pos, source);
+        }
+        lighting.spread(world, Channel::Block, lit);
+
+        lighting
+    }
+
+    /// how much sunlight reaches the block
+    pub fn sky(&self, pos: Position) -> u8 {
+        self.get(Channel::Sky, pos)
+    }
+
+    /// how much light from glowing blocks reaches the block
+    pub fn block(&self, pos: Position) -> u8 {
+        self.get(Channel::Block, pos)
+    }
+
+    /// the brighter of the two light levels
+    pub fn brightness(&self, pos: Position) -> u8 {
+        self.sky(pos).max(self.block(pos))
+    }
+
+    /// relights around a block that has changed in the world
+    pub fn update(&mut self, world: &World, pos: Position) {
+        let height = self.dims.height();
+        let column = pos / height as usize;
+        let old = self.sunlit[column];
+        let new = world.heights.top(&world.blocks, height, column);
+        self.sunlit[column] = new;
+
+        // blocks that went in or out of full sunlight
+        let bottom = column * height as usize;
+        let mut changed = vec![pos];
+        changed.extend((old.min(new)..old.max(new)).map(|y| bottom + y as usize));
+
+        self.relight(world, Channel::Sky, &changed);
+        self.relight(world, Channel::Block, &[pos]);
+    }
+
+    /// relights around a block update that has already been applied to the world
+    pub fn process_update(&mut self, world: &World, update: BlockUpdate) {
+        let pos = position::checked_from_ccb(self.dims, update.chunk, update.column, update.block);
+        if let Some(pos) = pos {
+            self.update(world, pos);
+        }
+    }
+
+    fn get(&self, channel: Channel, pos: Position) -> u8 {
+        match channel {
+            Channel::Sky => self.levels[pos] >> 4,
+            Channel::Block => self.levels[pos] & 0x0F,
+        }
+    }
+
+    fn set(&mut self, channel: Channel, pos: Position, level: u8) {
+        let levels = &mut self.levels[pos];
+        *levels = match channel {
+            Channel::Sky => (*levels & 0x0F) | level << 4,
+            Channel::Block => (*levels & 0xF0) | level,
+        };
+    }
+
+    /// the light the block has of its own, before any spreads to it
+    fn source(&self, world: &World, channel: Channel, pos: Position) -> u8 {
+        match channel {
+            Channel::Sky => {
+                let height = self.dims.height() as usize;
+                let sunlit = self.sunlit[pos / height] as usize;
+                if pos % height >= sunlit {
+                    MAX_LIGHT
+                } else {
+                    0
+                }
+            }
+            Channel::Block => world.get_block(pos).kind().properties().light,
+        }
+    }
+
+    /// spreads light out from every lit block in `lit` until it fades or is stopped
+    fn spread(&mut self, world: &World, channel: Channel, mut lit: VecDeque<Position>) {
+        while let Some(pos) = lit.pop_front() {
+            let level = self.get(channel, pos);
+            if level <= 1 {
+                continue;
+            }
+            for side in position::neighbors(self.dims, pos) {
+                let solid = world.get_block(side).kind().properties().solid;
+                if !solid && self.get(channel, side) < level - 1 {
+                    self.set(channel, side, level - 1);
+                    lit.push_back(side);
+                }
+            }
+        }
+    }
+
+    /// takes away all the light that could have come from the changed blocks,
+    /// then spreads light back in from their sources and anything still lit around them
+    fn relight(&mut self, world: &World, channel: Channel, changed: &[Position]) {
+        let mut dark = VecDeque::new();
+        let mut cleared = Vec::new();
+        for &pos in changed {
+            dark.push_back((pos, self.get(channel, pos)));
+            self.set(channel, pos, 0);
+            cleared.push(pos);
+        }
+
+        let mut lit = VecDeque::new();
+        while let Some((pos, level)) = dark.pop_front() {
+            for side in position::neighbors(self.dims, pos) {
+                let side_level = self.get(channel, side);
+                if side_level == 0 {
+                    continue;
+                }
+                if side_level < level {
+                    // may have been lit from here, so it goes dark too
+                    self.set(channel, side, 0);
+                    dark.push_back((side, side_level));
+                    cleared.push(side);
+                } else {
+                    lit.push_back(side);
+                }
+            }
+        }
+
+        for pos in cleared {
+            let source = self.source(world, channel, pos);
+            if source > 0 {
+                self.set(channel, pos, source);
+                lit.push_back(pos);
+            }
+        }
+        self.spread(world, channel, lit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::world::{
+        block::{BlockUpdate, TYPE_AIR, TYPE_LAVA, TYPE_STONE},
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::{Lighting, MAX_LIGHT};
+
+    /// a 32 wide world of stone up to y=10 with a hollow cave inside
+    fn cave() -> World {
+        let dims = Dimensions::new(32, 32, 16).unwrap();
+        let mut world = World::with_dimensions(dims, 0);
+        for pos in position::in_box(dims, (0, 0, 0), (31, 10, 31)) {
+            world.get_block_mut(pos).data = TYPE_STONE;
+        }
+        for pos in position::in_box(dims, (5, 3, 5), (15, 6, 15)) {
+            world.get_block_mut(pos).data = TYPE_AIR;
+        }
+        world
+    }
+
+    #[test]
+    fn test_sunlight_and_dark_caves() {
+        let world = cave();
+        let dims = world.dimensions();
+        let lighting = Lighting::new(&world);
+
+        assert_eq!(lighting.sky(position::from_xyz(dims, 3, 11, 3)), MAX_LIGHT);
+        assert_eq!(lighting.sky(position::from_xyz(dims, 3, 31, 3)), MAX_LIGHT);
+        assert_eq!(lighting.brightness(position::from_xyz(dims, 8, 4, 8)), 0);
+        assert_eq!(lighting.brightness(position::from_xyz(dims, 8, 8, 8)), 0);
+    }
+
+    #[test]
+    fn test_lava_lights_cave() {
+        let mut world = cave();
+        let dims = world.dimensions();
+        let mut lighting = Lighting::new(&world);
+
+        let lava = position::from_xyz(dims, 5, 3, 5);
+        world.get_block_mut(lava).data = TYPE_LAVA;
+        lighting.update(&world, lava);
+
+        assert_eq!(lighting.block(lava), MAX_LIGHT);
+        assert_eq!(lighting.block(position::from_xyz(dims, 6, 3, 5)), 14);
+        assert_eq!(lighting.block(position::from_xyz(dims, 7, 4, 6)), 11);
+        // the cave walls stop it
+        assert_eq!(lighting.block(position::from_xyz(dims, 5, 3, 4)), 0);
+
+        world.get_block_mut(lava).data = TYPE_AIR;
+        lighting.update(&world, lava);
+        assert_eq!(lighting.block(position::from_xyz(dims, 7, 4, 6)), 0);
+    }
+
+    #[test]
+    fn test_opening_the_roof() {
+        let mut world = cave();
+        let dims = world.dimensions();
+        let mut lighting = Lighting::new(&world);
+
+        for y in 7..=10 {
+            let pos = position::from_xyz(dims, 10, y, 10);
+            world.get_block_mut(pos).data = TYPE_AIR;
+            lighting.update(&world, pos);
+        }
+        // the shaft is open to the sky all the way down to the cave floor
+        assert_eq!(lighting.sky(position::from_xyz(dims, 10, 3, 10)), MAX_LIGHT);
+        assert_eq!(lighting.sky(position::from_xyz(dims, 12, 3, 10)), 13);
+    }
+
+    #[test]
+    fn test_updates_match_relighting_from_scratch() {
+        let mut world = cave();
+        let dims = world.dimensions();
+        let mut lighting = Lighting::new(&world);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for _ in 0..300 {
+            let (x, y, z) = (
+                rng.gen_range(0..32),
+                rng.gen_range(0..16),
+                rng.gen_range(0..32),
+            );
+            let data = [TYPE_AIR, TYPE_STONE, TYPE_LAVA][rng.gen_range(0..3)];
+            let update = BlockUpdate::new(dims, position::from_xyz(dims, x, y, z), data);
+            world.process_update(update);
+            lighting.process_update(&world, update);
+        }
+
+        let fresh = Lighting::new(&world);
+        for pos in 0..dims.volume() {
+            assert_eq!(
+                lighting.sky(pos),
+                fresh.sky(pos),
+                "{:?}",
+                position::to_xyz(dims, pos)
+            );
+            assert_eq!(
+                lighting.block(pos),
+                fresh.block(pos),
+                "{:?}",
+                position::to_xyz(dims, pos)
+            );
+        }
+    }
+}
@@ -1,56 +1,311 @@
+use std::{io, path::Path};
+
 use block::Block;
 
-use self::{block::BlockUpdate, position::Position};
+use self::{
+    block::{BlockKind, BlockUpdate},
+    heightmap::Heightmap,
+    position::{Dimensions, Position},
+    schedule::Schedule,
+    storage::Storage,
+    temperature::Temperature,
+    weather::Weather,
+};
 
+mod behavior;
 pub mod block;
 mod generation;
+mod heightmap;
+mod light;
 pub mod position;
+mod raycast;
+mod save;
+mod schedule;
+mod storage;
+mod temperature;
 pub mod update;
+mod weather;
 
-const SINGLE: usize = 256;
-const DOUBLE: usize = 256 * 256;
-const TRIPLE: usize = 256 * 256 * 256;
+pub use behavior::{IllRules, InvalidIllRules};
+pub use generation::{GenerationConfig, InvalidGenerationConfig};
+pub use light::Lighting;
+pub use raycast::RayHit;
+pub use schedule::{Reason, ScheduledTick};
+pub use temperature::{AMBIENT, BOILING};
+pub use weather::WeatherState;
 
 pub struct World {
+    /// the size of the world, which every position in it depends on
+    dims: Dimensions,
     /// A collection of blocks all together.
     /// These are stored in chunk-column-block order
-    /// - there are 256 chunks stored sequentially
-    /// - within each chunk are 256 sequentially stored columns
-    /// - within each column are 256 sequentially stored blocks
-    blocks: Vec<Block>,
+    /// - there are up to 256 chunks stored sequentially
+    /// - within each chunk are up to 256 sequentially stored columns
+    /// - within each column are up to 256 sequentially stored blocks
+    ///
+    /// see the `storage` module for how they're kept in memory
+    blocks: Storage,
+    /// seeds all randomness in the simulation so that runs can be repeated
+    seed: u64,
+    /// how many ticks have been simulated
+    ticks: u64,
+    /// one flag per column, in the same order as `blocks`. A column is flagged
+    /// whenever it changes, so that it and its neighbors get simulated next tick
+    active: Vec<bool>,
+    /// the surface of every column, kept up to date as blocks are written
+    heights: Heightmap,
+    /// blocks waiting to be ticked later
+    schedule: Schedule,
+    /// how many blocks each chunk picks at random to tick every tick
+    random_ticks: u16,
+    /// the clouds above the world
+    weather: Weather,
+    /// how hot it is all over the world, cell by cell
+    temperature: Temperature,
+    /// how the ill spreads
+    ill_rules: IllRules,
+}
+
+/// something that happened in the world that matters to the game as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    /// the ill reached the shrine at this position and took it
+    ShrineReached(Position),
+}
+
+/// what happened during one tick of the whole world
+pub struct TickReport {
+    /// every change made, to be sent out
+    pub updates: Vec<BlockUpdate>,
+    /// everything that happened that matters to the game, in the order it happened
+    pub events: Vec<GameEvent>,
+    /// how many chunks had columns to visit or scheduled ticks, not counting random ticks
+    pub chunks: usize,
 }
 
 impl World {
     /// constructs world of all zeros
     pub fn empty() -> World {
-        let res = World {
-            blocks: vec![Block::default(); TRIPLE],
-        };
-        res
+        World::seeded(0)
+    }
+
+    /// constructs world of all zeros whose simulation is seeded by `seed`
+    pub fn seeded(seed: u64) -> World {
+        World::with_dimensions(Dimensions::DEFAULT, seed)
+    }
+
+    /// constructs world of all zeros of the given size, whose simulation is seeded by `seed`
+    pub fn with_dimensions(dims: Dimensions, seed: u64) -> World {
+        World {
+            dims,
+            blocks: Storage::flat(dims),
+            seed,
+            ticks: 0,
+            active: vec![false; dims.columns()],
+            heights: Heightmap::new(dims.columns()),
+            schedule: Schedule::default(),
+            random_ticks: behavior::DEFAULT_RANDOM_TICKS,
+            weather: Weather::new(dims),
+            temperature: Temperature::new(dims),
+            ill_rules: IllRules::default(),
+        }
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        self.dims
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
     }
 
     /// get an immutable reference to the block at the given position
     pub fn get_block(&self, pos: Position) -> &Block {
-        &self.blocks[pos]
+        self.blocks.get(pos)
     }
 
-    /// get a mutable reference to the block at the given position
+    /// get a mutable reference to the block at the given position.
+    /// Its column is assumed to change, so it gets simulated next tick, and
+    /// any ticks the block was waiting on are cancelled
     pub fn get_block_mut(&mut self, pos: Position) -> &mut Block {
-        &mut self.blocks[pos]
+        self.wake(pos);
+        self.schedule.clear(pos);
+        let height = self.dims.height();
+        let (column, y) = (pos / height as usize, pos % height as usize);
+        self.heights.write(column, y as u16);
+        self.temperature.touch(pos);
+        self.blocks.get_mut(pos)
+    }
+
+    /// makes sure the block's column gets simulated next tick, even if nothing in it changes
+    pub fn wake(&mut self, pos: Position) {
+        self.active[pos / self.dims.height() as usize] = true;
+    }
+
+    /// makes sure every column gets simulated next tick
+    pub fn wake_all(&mut self) {
+        self.active.fill(true);
+    }
+
+    /// ticks the block `delay` ticks from now, unless it's already waiting
+    /// on one for the same reason. Returns whether it was scheduled
+    pub fn schedule(&mut self, pos: Position, delay: u64, reason: Reason) -> bool {
+        self.schedule.add(ScheduledTick {
+            tick: self.ticks + delay,
+            pos,
+            reason,
+        })
+    }
+
+    /// every tick that's been scheduled, in the order they'll be handled
+    pub fn scheduled(&self) -> impl Iterator<Item = &ScheduledTick> {
+        self.schedule.iter()
+    }
+
+    /// how many blocks each chunk picks at random to tick every tick
+    pub fn random_ticks(&self) -> u16 {
+        self.random_ticks
+    }
+
+    /// sets how many blocks each chunk randomly ticks every tick, which speeds
+    /// up or slows down slow processes like weathering. This isn't saved with the world
+    pub fn set_random_ticks(&mut self, random_ticks: u16) {
+        self.random_ticks = random_ticks;
+    }
+
+    pub fn ill_rules(&self) -> &IllRules {
+        &self.ill_rules
     }
 
-    /// update the world given this specific update
-    pub fn process_update(&mut self, update: BlockUpdate) {
-        self.get_block_mut(position::from_ccb(update.chunk, update.column, update.block)).data = update.new_data;
+    /// changes how the ill spreads from now on, unless the rules are invalid
+    /// (see [`IllRules::validate`]). This isn't saved with the world
+    pub fn set_ill_rules(&mut self, rules: IllRules) -> Result<(), InvalidIllRules> {
+        self.ill_rules = rules.validate()?;
+        Ok(())
     }
 
-    /// simulates one "tick" at the given chunk
-    pub fn simulate(&mut self, chunk: u8) {
-        todo!()
+    /// how cloudy and rainy it is across the whole world
+    pub fn weather(&self) -> WeatherState {
+        self.weather.state()
     }
 
-    /// generates a new world by the generation algorithm
-    pub fn generate() -> World {
-        generation::generate()
+    /// how hot it is around the block, from its cell of the temperature field.
+    /// Mostly useful for debugging, see the `temperature` module
+    pub fn temperature(&self, pos: Position) -> u8 {
+        self.temperature.at(pos)
     }
-}
\ No newline at end of file
+
+    /// packs the world's chunks to save memory. Chunks unpack as they're
+    /// written to, so this is worth calling again every so often
+    pub fn compact(&mut self) {
+        self.blocks.compact();
+    }
+
+    /// roughly how many bytes the world's blocks take up
+    pub fn memory_usage(&self) -> usize {
+        self.blocks.memory_usage()
+    }
+
+    /// update the world given this specific update. Returns `false` without
+    /// changing anything if the update is for a block outside of the world,
+    /// or its data isn't a valid block (see [`Block::try_from`])
+    pub fn process_update(&mut self, update: BlockUpdate) -> bool {
+        let pos = position::checked_from_ccb(self.dims, update.chunk, update.column, update.block);
+        match (pos, Block::try_from(update.new_data)) {
+            (Some(pos), Ok(block)) => {
+                *self.get_block_mut(pos) = block;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// simulates one "tick" of the whole world, returning every change it made
+    /// so they can be sent out. Only columns that changed last tick and the
+    /// columns beside them are simulated, along with scheduled and random ticks
+    ///
+    /// `states` holds the internal state of each block, like how much water it holds
+    pub fn simulate(&mut self, states: &mut World) -> TickReport {
+        self.simulate_threaded(states, behavior::default_threads())
+    }
+
+    /// like [`World::simulate`], but spreads the chunks over at most `threads` threads.
+    /// Gives exactly the same result whatever the thread count
+    pub fn simulate_threaded(&mut self, states: &mut World, threads: usize) -> TickReport {
+        behavior::simulate(self, states, threads)
+    }
+
+    /// simulates every block in the given chunk, whether it's active or not.
+    /// Doesn't end the tick
+    pub fn simulate_chunk(&mut self, states: &mut World, chunk: u8) -> TickReport {
+        let columns = 0..self.dims.chunk_columns() as u16;
+        behavior::simulate_chunk(self, states, chunk, columns.map(|column| column as u8))
+    }
+
+    /// marks the end of a tick, once every chunk has been simulated
+    pub fn end_tick(&mut self) {
+        self.ticks += 1;
+        self.heights.settle(&self.blocks, self.dims.height());
+    }
+
+    /// the height of the highest block in the column at x, z that isn't air,
+    /// or `None` if the whole column is air
+    pub fn surface_height(&self, x: i16, z: i16) -> Option<i16> {
+        let height = self.dims.height();
+        let column = position::from_xyz(self.dims, x, 0, z) / height as usize;
+        let top = self.heights.top(&self.blocks, height, column);
+        (top > 0).then(|| top as i16 - 1)
+    }
+
+    /// sets the block on fire if it can burn, e.g. when a cannon shot lands on it.
+    /// Returns the change if it caught
+    pub fn ignite(&mut self, states: &mut World, pos: Position) -> Option<BlockUpdate> {
+        behavior::ignite(self, states, pos)
+    }
+
+    /// the first block that isn't air within `max_dist` of `origin` along `dir`,
+    /// e.g. the block the player is pointing at
+    pub fn raycast(
+        &self,
+        origin: (f32, f32, f32),
+        dir: (f32, f32, f32),
+        max_dist: f32,
+    ) -> Option<RayHit> {
+        raycast::raycast(self, origin, dir, max_dist, |block| {
+            block.kind() != BlockKind::Air
+        })
+    }
+
+    /// like [`World::raycast`], but stops at the first block `hits` accepts.
+    /// Useful for seeing through some blocks, e.g. anything not solid
+    pub fn raycast_until(
+        &self,
+        origin: (f32, f32, f32),
+        dir: (f32, f32, f32),
+        max_dist: f32,
+        hits: impl FnMut(Block) -> bool,
+    ) -> Option<RayHit> {
+        raycast::raycast(self, origin, dir, max_dist, hits)
+    }
+
+    /// writes the world to a file, see the `save` module for the format
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        save::save(self, path.as_ref())
+    }
+
+    /// reads a world written by [`World::save`]. Fails on any file that
+    /// is truncated, corrupted or otherwise not a valid world
+    pub fn load(path: impl AsRef<Path>) -> io::Result<World> {
+        save::load(path.as_ref())
+    }
+
+    /// generates a new world by the generation algorithm, unless the config is
+    /// invalid (see [`GenerationConfig::validate`])
+    pub fn generate(config: &GenerationConfig) -> Result<World, InvalidGenerationConfig> {
+        generation::generate(config)
+    }
+}
@@ -1,45 +1,448 @@
-use super::{DOUBLE, SINGLE};
+use std::{error::Error, fmt};
+
+use super::block::Direction;
 
 pub type Position = usize;
 
+/// the size of a world. A position only means anything alongside the dimensions of its world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Dimensions {
+    /// blocks along x and z. The world wraps around after this many
+    width: u16,
+    /// blocks along y
+    height: u16,
+    /// blocks along each side of a chunk
+    chunk_width: u16,
+}
+
+impl Dimensions {
+    /// a 256x256x256 world in 16x16 chunks
+    pub const DEFAULT: Dimensions = Dimensions {
+        width: 256,
+        height: 256,
+        chunk_width: 16,
+    };
+
+    /// a world `width` blocks along x and z and `height` blocks tall, split into
+    /// chunks `chunk_width` blocks across
+    ///
+    /// each part of a `BlockUpdate` is a byte, so there can be at most 256
+    /// chunks, 256 columns in a chunk and 256 blocks in a column. Both widths
+    /// have to be powers of two and chunks at least 2 wide, so that the chunks
+    /// can be simulated in a checkerboard
+    pub fn new(width: u16, height: u16, chunk_width: u16) -> Result<Dimensions, InvalidDimensions> {
+        let valid = chunk_width.is_power_of_two()
+            && (2..=16).contains(&chunk_width)
+            && width.is_power_of_two()
+            && (chunk_width..=chunk_width * 16).contains(&width)
+            && (1..=256).contains(&height);
+
+        let dims = Dimensions {
+            width,
+            height,
+            chunk_width,
+        };
+        if valid {
+            Ok(dims)
+        } else {
+            Err(InvalidDimensions(dims))
+        }
+    }
+
+    pub fn width(self) -> u16 {
+        self.width
+    }
+
+    pub fn height(self) -> u16 {
+        self.height
+    }
+
+    pub fn chunk_width(self) -> u16 {
+        self.chunk_width
+    }
+
+    /// how many chunks there are along x, and along z
+    pub fn chunks_across(self) -> u16 {
+        self.width / self.chunk_width
+    }
+
+    /// how many chunks there are in the world
+    pub fn chunks(self) -> usize {
+        self.chunks_across() as usize * self.chunks_across() as usize
+    }
+
+    /// how many columns there are in each chunk
+    pub fn chunk_columns(self) -> usize {
+        self.chunk_width as usize * self.chunk_width as usize
+    }
+
+    /// how many columns there are in the world
+    pub fn columns(self) -> usize {
+        self.width as usize * self.width as usize
+    }
+
+    /// how many blocks there are in each chunk
+    pub fn chunk_volume(self) -> usize {
+        self.chunk_columns() * self.height as usize
+    }
+
+    /// how many blocks there are in the world
+    pub fn volume(self) -> usize {
+        self.columns() * self.height as usize
+    }
+}
+
+impl Default for Dimensions {
+    fn default() -> Self {
+        Dimensions::DEFAULT
+    }
+}
+
+/// dimensions that break one of the rules in [`Dimensions::new`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidDimensions(pub Dimensions);
+
+impl fmt::Display for InvalidDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Dimensions {
+            width,
+            height,
+            chunk_width,
+        } = self.0;
+        write!(
+            f,
+            "invalid world size {width}x{height}x{width} with {chunk_width} wide chunks"
+        )
+    }
+}
+
+impl Error for InvalidDimensions {}
+
 /// from chunk,column,block coordinates
-/// 
+///
 /// # Arguments
-/// * `chunk` - the chunk number (0-255)
-/// * `column` - the column number (0-255)
-/// * `block` - the block number (0-255)
+/// * `dims` - the dimensions of the world
+/// * `chunk` - the chunk number
+/// * `column` - the column number within the chunk
+/// * `block` - the block number within the column
+#[inline]
+pub fn from_ccb(dims: Dimensions, chunk: u8, column: u8, block: u8) -> Position {
+    chunk as usize * dims.chunk_volume() + column as usize * dims.height as usize + block as usize
+}
+
+/// from chunk,column,block coordinates like `from_ccb`, except that
+/// coordinates outside of the world give `None`
 #[inline]
-pub fn from_ccb(chunk: u8, column: u8, block: u8) -> Position {
-    chunk as usize * DOUBLE + column as usize * SINGLE + block as usize
+pub fn checked_from_ccb(dims: Dimensions, chunk: u8, column: u8, block: u8) -> Option<Position> {
+    let inside = (chunk as usize) < dims.chunks()
+        && (column as usize) < dims.chunk_columns()
+        && (block as u16) < dims.height;
+    inside.then(|| from_ccb(dims, chunk, column, block))
 }
 
 /// from x,y,z coordinates
 ///
 /// # Arguments
+/// * `dims` - the dimensions of the world
 /// * `x` - x position (east-west)
 /// * `y` - y position (height)
 /// * `z` - z position (north-south)
 #[inline]
-pub fn from_xyz(x: i16, y: i16, z: i16) -> Position {
-    let x = x.rem_euclid(256) as u8;
-    let y = y.clamp(0, 255);
-    let z = z.rem_euclid(256) as u8;
+pub fn from_xyz(dims: Dimensions, x: i16, y: i16, z: i16) -> Position {
+    let (width, chunk_width) = (dims.width as i16, dims.chunk_width as i16);
+    let x = x.rem_euclid(width);
+    let y = y.clamp(0, dims.height as i16 - 1);
+    let z = z.rem_euclid(width);
+
+    let chunk = (x / chunk_width + z / chunk_width * dims.chunks_across() as i16) as u8;
+    let column = (x % chunk_width + z % chunk_width * chunk_width) as u8;
+
+    from_ccb(dims, chunk, column, y as u8)
+}
+
+pub fn chunk(dims: Dimensions, pos: Position) -> u8 {
+    (pos / dims.chunk_volume()) as u8
+}
+
+pub fn column(dims: Dimensions, pos: Position) -> u8 {
+    (pos / dims.height as usize % dims.chunk_columns()) as u8
+}
+
+pub fn block(dims: Dimensions, pos: Position) -> u8 {
+    (pos % dims.height as usize) as u8
+}
+
+/// to x,y,z coordinates, the inverse of `from_xyz`.
+/// x and z come back between 0 and the world's width
+#[inline]
+pub fn to_xyz(dims: Dimensions, pos: Position) -> (i16, i16, i16) {
+    let (chunk, column) = (chunk(dims, pos) as i16, column(dims, pos) as i16);
+    let (across, chunk_width) = (dims.chunks_across() as i16, dims.chunk_width as i16);
+    let x = chunk % across * chunk_width + column % chunk_width;
+    let z = chunk / across * chunk_width + column / chunk_width;
+    (x, block(dims, pos) as i16, z)
+}
+
+/// from x,y,z coordinates like `from_xyz`, except that a y outside of the
+/// world gives `None` instead of being clamped
+#[inline]
+pub fn checked_from_xyz(dims: Dimensions, x: i16, y: i16, z: i16) -> Option<Position> {
+    (0..dims.height as i16)
+        .contains(&y)
+        .then(|| from_xyz(dims, x, y, z))
+}
+
+/// the next block over in the given direction, wrapping around in x and z.
+/// Like `from_xyz`, going past the top or bottom stays put
+#[inline]
+pub fn neighbor(dims: Dimensions, pos: Position, dir: Direction) -> Position {
+    checked_neighbor(dims, pos, dir).unwrap_or(pos)
+}
 
-    let chunk = ((x / 16) | (z & 0b11110000)) as usize;
-    let column = ((x % 16) | (16 * (z % 16))) as usize;
-    let block = y as usize;
+/// the next block over in the given direction, wrapping around in x and z.
+/// `None` past the top or bottom of the world
+#[inline]
+pub fn checked_neighbor(dims: Dimensions, pos: Position, dir: Direction) -> Option<Position> {
+    let (x, y, z) = to_xyz(dims, pos);
+    let (dx, dy, dz) = dir.offset();
+    checked_from_xyz(dims, x + dx, y + dy, z + dz)
+}
+
+/// the blocks sharing a face with this one, in the order of `Direction::ALL`.
+/// Blocks past the top or bottom of the world are left out
+pub fn neighbors(dims: Dimensions, pos: Position) -> impl Iterator<Item = Position> {
+    Direction::ALL
+        .into_iter()
+        .filter_map(move |dir| checked_neighbor(dims, pos, dir))
+}
+
+/// the four blocks beside this one at the same height
+pub fn horizontal_neighbors(dims: Dimensions, pos: Position) -> impl Iterator<Item = Position> {
+    [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ]
+    .into_iter()
+    .map(move |dir| neighbor(dims, pos, dir))
+}
+
+/// the shortest signed step from `from` to `to` along x or z, which wrap
+/// around every `width` blocks. Between -width/2 and width/2 - 1
+#[inline]
+pub fn wrap_delta(dims: Dimensions, from: i16, to: i16) -> i16 {
+    // worked out wider so coordinates far from the world can't overflow
+    let (width, half) = (dims.width as i32, dims.width as i32 / 2);
+    ((to as i32 - from as i32 + half).rem_euclid(width) - half) as i16
+}
+
+/// the shortest (x, y, z) offset from one position to another,
+/// going across the seam in x or z if that's closer. y doesn't wrap
+pub fn delta(dims: Dimensions, from: Position, to: Position) -> (i16, i16, i16) {
+    let (fx, fy, fz) = to_xyz(dims, from);
+    let (tx, ty, tz) = to_xyz(dims, to);
+    (wrap_delta(dims, fx, tx), ty - fy, wrap_delta(dims, fz, tz))
+}
+
+/// the squared straight-line distance between two positions the short way around
+pub fn distance_squared(dims: Dimensions, a: Position, b: Position) -> i32 {
+    let (dx, dy, dz) = delta(dims, a, b);
+    let (dx, dy, dz) = (dx as i32, dy as i32, dz as i32);
+    dx * dx + dy * dy + dz * dz
+}
 
-    chunk * DOUBLE + column * SINGLE + block
+/// the straight-line distance between two positions the short way around
+pub fn distance(dims: Dimensions, a: Position, b: Position) -> f32 {
+    (distance_squared(dims, a, b) as f32).sqrt()
 }
 
-pub fn chunk(pos: Position) -> u8 {
-    (pos / DOUBLE) as u8
+/// every position in the box between the corners `min` and `max`, inclusive.
+/// x and z can run past the seam (e.g. from 250 to 260) and wrap around, but a
+/// box wider than the world only covers it once.
+/// Any part of the box above or below the world is left out
+pub fn in_box(
+    dims: Dimensions,
+    min: (i16, i16, i16),
+    max: (i16, i16, i16),
+) -> impl Iterator<Item = Position> {
+    let (min_y, max_y) = (min.1.max(0), max.1.min(dims.height as i16 - 1));
+    let span =
+        move |min: i16, max: i16| min..=(max as i32).min(min as i32 + dims.width as i32 - 1) as i16;
+    span(min.0, max.0).flat_map(move |x| {
+        span(min.2, max.2).flat_map(move |z| (min_y..=max_y).map(move |y| from_xyz(dims, x, y, z)))
+    })
 }
 
-pub fn column(pos: Position) -> u8 {
-    ((pos / SINGLE) % SINGLE) as u8
+/// every position within `radius` blocks of `center` along each axis
+pub fn around(dims: Dimensions, center: Position, radius: i16) -> impl Iterator<Item = Position> {
+    let (x, y, z) = to_xyz(dims, center);
+    in_box(
+        dims,
+        (x - radius, y - radius, z - radius),
+        (x + radius, y + radius, z + radius),
+    )
 }
 
-pub fn block(pos: Position) -> u8 {
-    (pos % SINGLE) as u8
+#[cfg(test)]
+mod tests {
+    use crate::world::block::Direction;
+
+    use super::{
+        around, checked_from_ccb, checked_from_xyz, checked_neighbor, delta, distance, from_xyz,
+        in_box, neighbor, neighbors, to_xyz, wrap_delta, Dimensions,
+    };
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    #[test]
+    fn test_to_xyz() {
+        for (x, y, z) in [(0, 0, 0), (15, 16, 17), (200, 255, 3), (255, 128, 255)] {
+            assert_eq!(to_xyz(DIMS, from_xyz(DIMS, x, y, z)), (x, y, z));
+        }
+        assert_eq!(to_xyz(DIMS, from_xyz(DIMS, -1, 4, 256)), (255, 4, 0));
+    }
+
+    #[test]
+    fn test_neighbor_across_chunks() {
+        let pos = from_xyz(DIMS, 15, 10, 31);
+        assert_eq!(
+            to_xyz(DIMS, neighbor(DIMS, pos, Direction::East)),
+            (16, 10, 31)
+        );
+        assert_eq!(
+            to_xyz(DIMS, neighbor(DIMS, pos, Direction::South)),
+            (15, 10, 32)
+        );
+        assert_eq!(
+            to_xyz(
+                DIMS,
+                neighbor(DIMS, from_xyz(DIMS, 0, 0, 0), Direction::West)
+            ),
+            (255, 0, 0)
+        );
+        assert_eq!(
+            to_xyz(
+                DIMS,
+                neighbor(DIMS, from_xyz(DIMS, 0, 0, 0), Direction::North)
+            ),
+            (0, 0, 255)
+        );
+    }
+
+    #[test]
+    fn test_checked_at_edges() {
+        let bottom = from_xyz(DIMS, 3, 0, 3);
+        let top = from_xyz(DIMS, 3, 255, 3);
+
+        assert_eq!(checked_neighbor(DIMS, bottom, Direction::Down), None);
+        assert_eq!(checked_neighbor(DIMS, top, Direction::Up), None);
+        assert_eq!(neighbor(DIMS, top, Direction::Up), top);
+        assert_eq!(checked_from_xyz(DIMS, 3, -1, 3), None);
+        assert_eq!(checked_from_xyz(DIMS, 3, 256, 3), None);
+
+        assert_eq!(neighbors(DIMS, bottom).count(), 5);
+        assert_eq!(neighbors(DIMS, from_xyz(DIMS, 3, 1, 3)).count(), 6);
+    }
+
+    #[test]
+    fn test_wrap_delta_at_seam() {
+        assert_eq!(wrap_delta(DIMS, 255, 0), 1);
+        assert_eq!(wrap_delta(DIMS, 0, 255), -1);
+        assert_eq!(wrap_delta(DIMS, 250, 5), 11);
+        assert_eq!(wrap_delta(DIMS, 5, 250), -11);
+        assert_eq!(wrap_delta(DIMS, 0, 128), -128);
+        assert_eq!(wrap_delta(DIMS, 0, 127), 127);
+        assert_eq!(wrap_delta(DIMS, 10, 20), 10);
+        // far outside the world, where the difference doesn't fit in an i16
+        assert_eq!(wrap_delta(DIMS, i16::MIN, i16::MAX), -1);
+        assert_eq!(wrap_delta(DIMS, i16::MAX, i16::MIN), 1);
+    }
+
+    #[test]
+    fn test_distance_at_seam() {
+        let east = from_xyz(DIMS, 255, 10, 0);
+        let west = from_xyz(DIMS, 0, 10, 0);
+        assert_eq!(delta(DIMS, east, west), (1, 0, 0));
+        assert_eq!(delta(DIMS, west, east), (-1, 0, 0));
+        assert_eq!(distance(DIMS, east, west), 1.0);
+
+        let corner = from_xyz(DIMS, 255, 0, 255);
+        let origin = from_xyz(DIMS, 0, 4, 0);
+        assert_eq!(delta(DIMS, corner, origin), (1, 4, 1));
+        assert_eq!(distance(DIMS, corner, origin), 18f32.sqrt());
+
+        // y never wraps
+        assert_eq!(
+            distance(DIMS, from_xyz(DIMS, 0, 0, 0), from_xyz(DIMS, 0, 255, 0)),
+            255.0
+        );
+    }
+
+    #[test]
+    fn test_box_across_seam() {
+        let xs = in_box(DIMS, (254, 0, 0), (257, 0, 0))
+            .map(|pos| to_xyz(DIMS, pos).0)
+            .collect::<Vec<_>>();
+        assert_eq!(xs, [254, 255, 0, 1]);
+
+        assert_eq!(around(DIMS, from_xyz(DIMS, 0, 5, 255), 1).count(), 27);
+        assert!(
+            around(DIMS, from_xyz(DIMS, 0, 5, 255), 1).any(|pos| to_xyz(DIMS, pos) == (255, 4, 0))
+        );
+
+        // cut off at the bottom of the world
+        assert_eq!(around(DIMS, from_xyz(DIMS, 0, 0, 0), 1).count(), 18);
+
+        // wider than the world, so every column comes up once
+        let dims = Dimensions::new(32, 8, 8).unwrap();
+        let mut columns = in_box(dims, (-40, 0, 5), (40, 0, 100)).collect::<Vec<_>>();
+        assert_eq!(columns.len(), 32 * 32);
+        columns.sort();
+        columns.dedup();
+        assert_eq!(columns.len(), 32 * 32);
+        assert_eq!(
+            in_box(dims, (i16::MAX - 1, 0, 0), (i16::MAX, 0, 0)).count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_small_world() {
+        let dims = Dimensions::new(64, 32, 16).unwrap();
+        assert_eq!(dims.chunks(), 16);
+        assert_eq!(dims.volume(), 64 * 64 * 32);
+
+        let mut seen = vec![false; dims.volume()];
+        for x in 0..64 {
+            for y in 0..32 {
+                for z in 0..64 {
+                    let pos = from_xyz(dims, x, y, z);
+                    assert_eq!(to_xyz(dims, pos), (x, y, z));
+                    assert!(!seen[pos]);
+                    seen[pos] = true;
+                }
+            }
+        }
+
+        assert_eq!(to_xyz(dims, from_xyz(dims, -1, 40, 64)), (63, 31, 0));
+        assert_eq!(wrap_delta(dims, 63, 0), 1);
+        assert_eq!(wrap_delta(dims, 0, 32), -32);
+        assert_eq!(checked_from_xyz(dims, 0, 32, 0), None);
+        assert_eq!(checked_from_ccb(dims, 16, 0, 0), None);
+        assert_eq!(checked_from_ccb(dims, 15, 255, 31), Some(dims.volume() - 1));
+    }
+
+    #[test]
+    fn test_invalid_dimensions() {
+        assert!(Dimensions::new(256, 256, 16).is_ok());
+        assert!(Dimensions::new(16, 1, 16).is_ok());
+        // more than 256 chunks
+        assert!(Dimensions::new(512, 256, 16).is_err());
+        // more than 256 columns in a chunk
+        assert!(Dimensions::new(256, 256, 32).is_err());
+        assert!(Dimensions::new(256, 257, 16).is_err());
+        assert!(Dimensions::new(96, 64, 16).is_err());
+        assert!(Dimensions::new(64, 64, 1).is_err());
+    }
 }
@@ -0,0 +1,249 @@
+//! finding the first block along a ray
+//!
+//! rays step from block to block with the voxel traversal of Amanatides and
+//! Woo, so no block the ray passes through is skipped. Like everything else,
+//! rays wrap around in x and z
+
+use super::{
+    block::{Block, Direction},
+    position::{self, Position},
+    World,
+};
+
+/// where a ray hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub pos: Position,
+    /// the face of the block the ray went in through. Placing a block
+    /// against this face puts it at `neighbor(pos, face)`
+    pub face: Direction,
+    /// how far along the ray the hit was, in blocks
+    pub distance: f32,
+}
+
+/// the first block within `max_dist` of `origin` along `dir` that `hits` says
+/// the ray stops at. `dir` doesn't need to be normalized. Since rays wrap
+/// around, they give up after going twice the world's width plus its height,
+/// however large `max_dist` is. Nothing is hit from an origin that isn't finite,
+/// or with a negative `max_dist`
+pub fn raycast(
+    world: &World,
+    origin: (f32, f32, f32),
+    dir: (f32, f32, f32),
+    max_dist: f32,
+    mut hits: impl FnMut(Block) -> bool,
+) -> Option<RayHit> {
+    let len = (dir.0 * dir.0 + dir.1 * dir.1 + dir.2 * dir.2).sqrt();
+    let finite = [origin.0, origin.1, origin.2].iter().all(|o| o.is_finite());
+    if len == 0.0 || !len.is_finite() || !finite || max_dist.is_nan() || max_dist < 0.0 {
+        return None;
+    }
+    let dir = [dir.0 / len, dir.1 / len, dir.2 / len];
+
+    let dims = world.dimensions();
+    let (width, height) = (dims.width() as f32, dims.height() as i32);
+    let max_dist = max_dist.min(width * 2.0 + height as f32);
+    // wrapping x and z into the world keeps the voxel coordinates from overflowing
+    let origin = [
+        origin.0.rem_euclid(width),
+        origin.1.clamp(-1.0 - max_dist, height as f32 + max_dist),
+        origin.2.rem_euclid(width),
+    ];
+    let mut voxel = origin.map(|o| o.floor() as i32);
+    let step = dir.map(|d| {
+        if d > 0.0 {
+            1
+        } else if d < 0.0 {
+            -1
+        } else {
+            0
+        }
+    });
+
+    // how far along the ray the next boundary is on each axis, and how far apart boundaries are
+    let mut next = [f32::INFINITY; 3];
+    let mut spacing = [f32::INFINITY; 3];
+    for axis in 0..3 {
+        if step[axis] != 0 {
+            let boundary = (voxel[axis] + (step[axis] + 1) / 2) as f32;
+            next[axis] = (boundary - origin[axis]) / dir[axis];
+            spacing[axis] = 1.0 / dir[axis].abs();
+        }
+    }
+
+    // a ray starting inside a block is said to go in through the face it points away from
+    let main_axis = (0..3)
+        .max_by(|&a, &b| dir[a].abs().total_cmp(&dir[b].abs()))
+        .unwrap();
+    let mut face = entered_through(main_axis, step[main_axis]);
+    let mut distance = 0.0;
+
+    loop {
+        let y = voxel[1];
+        if (0..height).contains(&y) {
+            let (width, x, z) = (dims.width() as i32, voxel[0], voxel[2]);
+            let pos = position::from_xyz(
+                dims,
+                x.rem_euclid(width) as i16,
+                y as i16,
+                z.rem_euclid(width) as i16,
+            );
+            if hits(*world.get_block(pos)) {
+                return Some(RayHit {
+                    pos,
+                    face,
+                    distance,
+                });
+            }
+        } else if (y < 0 && step[1] <= 0) || (y >= height && step[1] >= 0) {
+            // outside the world and never coming back
+            return None;
+        }
+
+        let axis = (0..3).min_by(|&a, &b| next[a].total_cmp(&next[b])).unwrap();
+        distance = next[axis];
+        if distance > max_dist {
+            return None;
+        }
+        voxel[axis] += step[axis];
+        next[axis] += spacing[axis];
+        face = entered_through(axis, step[axis]);
+    }
+}
+
+/// the face a ray goes in through when it steps along `axis` (x, y or z) by `step`
+fn entered_through(axis: usize, step: i32) -> Direction {
+    match (axis, step > 0) {
+        (0, true) => Direction::West,
+        (0, false) => Direction::East,
+        (1, true) => Direction::Down,
+        (1, false) => Direction::Up,
+        (_, true) => Direction::North,
+        (_, false) => Direction::South,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{Direction, TYPE_STONE},
+        position::{self, Dimensions},
+        World,
+    };
+
+    const DIMS: Dimensions = Dimensions::DEFAULT;
+
+    fn world_with_stone_at(blocks: &[(i16, i16, i16)]) -> World {
+        let mut world = World::empty();
+        for &(x, y, z) in blocks {
+            world.get_block_mut(position::from_xyz(DIMS, x, y, z)).data = TYPE_STONE;
+        }
+        world
+    }
+
+    #[test]
+    fn test_straight_down() {
+        let world = world_with_stone_at(&[(3, 2, 3)]);
+
+        let hit = world
+            .raycast((3.5, 10.5, 3.5), (0.0, -1.0, 0.0), 20.0)
+            .unwrap();
+        assert_eq!(hit.pos, position::from_xyz(DIMS, 3, 2, 3));
+        assert_eq!(hit.face, Direction::Up);
+        assert_eq!(hit.distance, 7.5);
+
+        assert!(world
+            .raycast((3.5, 10.5, 3.5), (0.0, -1.0, 0.0), 7.0)
+            .is_none());
+        assert!(world
+            .raycast((3.5, 10.5, 3.5), (0.0, 1.0, 0.0), 1000.0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_across_seam() {
+        let world = world_with_stone_at(&[(1, 5, 0), (0, 5, 254)]);
+
+        let hit = world
+            .raycast((254.5, 5.5, 0.5), (1.0, 0.0, 0.0), 10.0)
+            .unwrap();
+        assert_eq!(hit.pos, position::from_xyz(DIMS, 1, 5, 0));
+        assert_eq!(hit.face, Direction::West);
+        assert_eq!(hit.distance, 2.5);
+
+        // going north from z=1 wraps to the south edge
+        let hit = world
+            .raycast((0.5, 5.5, 1.5), (0.0, 0.0, -2.0), 10.0)
+            .unwrap();
+        assert_eq!(hit.pos, position::from_xyz(DIMS, 0, 5, 254));
+        assert_eq!(hit.face, Direction::South);
+        assert_eq!(hit.distance, 2.5);
+    }
+
+    #[test]
+    fn test_diagonal_hits_every_block_it_crosses() {
+        // the ray is rising towards (1, 1, 0), but crosses into (1, 0, 0) first
+        let world = world_with_stone_at(&[(1, 0, 0)]);
+        let hit = world
+            .raycast((0.5, 0.9, 0.5), (1.0, 0.1, 0.0), 10.0)
+            .unwrap();
+        assert_eq!(hit.pos, position::from_xyz(DIMS, 1, 0, 0));
+        assert_eq!(hit.face, Direction::West);
+
+        let from_below = world
+            .raycast((1.5, -3.0, 0.5), (0.0, 1.0, 0.0), 10.0)
+            .unwrap();
+        assert_eq!(from_below.face, Direction::Down);
+        assert_eq!(from_below.distance, 3.0);
+    }
+
+    #[test]
+    fn test_gives_up_in_open_air() {
+        let world = World::empty();
+        let endless = world.raycast((3.5, 10.5, 3.5), (1.0, 0.0, 0.3), f32::INFINITY);
+        assert_eq!(endless, None);
+        assert_eq!(
+            world.raycast((3.5, 10.5, 3.5), (1.0, 0.0, 0.0), f32::NAN),
+            None
+        );
+        assert_eq!(
+            world.raycast((f32::NAN, 10.5, 3.5), (1.0, 0.0, 0.0), 5.0),
+            None
+        );
+        assert_eq!(
+            world.raycast((f32::INFINITY, 10.5, 3.5), (1.0, 0.0, 0.0), 5.0),
+            None
+        );
+
+        // far out but finite origins still wrap onto the world
+        let world = world_with_stone_at(&[(3, 2, 3)]);
+        let far = 256.0 * 1000.0 + 3.5;
+        let hit = world
+            .raycast((far, 10.5, 3.5), (0.0, -1.0, 0.0), 20.0)
+            .unwrap();
+        assert_eq!(hit.pos, position::from_xyz(DIMS, 3, 2, 3));
+        let sky = world.raycast((3.5, 1e30, 3.5), (0.0, -1.0, 0.0), f32::INFINITY);
+        assert_eq!(sky, None);
+
+        // backwards isn't a direction a ray can go
+        for max_dist in [-1.0, -200.0, f32::NEG_INFINITY] {
+            let hit = world.raycast((3.5, 10.5, 3.5), (0.0, -1.0, 0.0), max_dist);
+            assert_eq!(hit, None);
+        }
+    }
+
+    #[test]
+    fn test_starting_inside() {
+        let world = world_with_stone_at(&[(3, 3, 3)]);
+        let hit = world
+            .raycast((3.5, 3.5, 3.5), (0.0, 0.0, 1.0), 10.0)
+            .unwrap();
+        assert_eq!(hit.pos, position::from_xyz(DIMS, 3, 3, 3));
+        assert_eq!(hit.face, Direction::North);
+        assert_eq!(hit.distance, 0.0);
+
+        assert!(world
+            .raycast((3.5, 3.5, 3.5), (0.0, 0.0, 0.0), 10.0)
+            .is_none());
+    }
+}
@@ -0,0 +1,334 @@
+//! saving worlds to disk
+//!
+//! the format, with every number little endian:
+//! - header: the magic bytes `ILLW`, a u16 version, the world's width,
+//!   height and chunk width as u16s, then its seed and tick count as u64s
+//! - the u32 number of scheduled ticks, then each one in the order they're
+//!   due: the tick as a u64, the position as a u32 and the reason as a u8
+//! - how much water the cloud above each chunk holds as a u32, in chunk order
+//! - every chunk in order, each starting with the u32 byte length of its body.
+//!   The body is run-length encoded blocks in chunk-column-block order,
+//!   each run being a u16 length (minus one) and then the block data
+//! - a u64 FNV-1a checksum of everything before it
+
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use super::{
+    block::Block,
+    position::Dimensions,
+    schedule::{Reason, ScheduledTick},
+    World,
+};
+
+const MAGIC: &[u8; 4] = b"ILLW";
+const VERSION: u16 = 1;
+
+pub fn save(world: &World, path: &Path) -> io::Result<()> {
+    fs::write(path, encode(world))
+}
+
+pub fn load(path: &Path) -> io::Result<World> {
+    decode(&fs::read(path)?)
+}
+
+fn encode(world: &World) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&world.dims.width().to_le_bytes());
+    out.extend_from_slice(&world.dims.height().to_le_bytes());
+    out.extend_from_slice(&world.dims.chunk_width().to_le_bytes());
+    out.extend_from_slice(&world.seed.to_le_bytes());
+    out.extend_from_slice(&world.ticks.to_le_bytes());
+
+    out.extend_from_slice(&(world.schedule.len() as u32).to_le_bytes());
+    for scheduled in world.schedule.iter() {
+        out.extend_from_slice(&scheduled.tick.to_le_bytes());
+        out.extend_from_slice(&(scheduled.pos as u32).to_le_bytes());
+        out.push(scheduled.reason as u8);
+    }
+
+    for cloud in &world.weather.clouds {
+        out.extend_from_slice(&cloud.to_le_bytes());
+    }
+
+    for chunk in 0..world.dims.chunks() {
+        let body = encode_chunk(&world.blocks.chunk(chunk));
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(&body);
+    }
+
+    let checksum = fnv1a(&out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out
+}
+
+fn encode_chunk(blocks: &[Block]) -> Vec<u8> {
+    let mut body = Vec::new();
+    let mut i = 0;
+    while i < blocks.len() {
+        let data = blocks[i].data;
+        let run = blocks[i..]
+            .iter()
+            .take(u16::MAX as usize + 1)
+            .take_while(|block| block.data == data)
+            .count();
+        body.extend_from_slice(&((run - 1) as u16).to_le_bytes());
+        body.push(data);
+        i += run;
+    }
+    body
+}
+
+fn decode(bytes: &[u8]) -> io::Result<World> {
+    if bytes.len() < 8 {
+        return Err(invalid("world file is truncated"));
+    }
+    let (contents, checksum) = bytes.split_at(bytes.len() - 8);
+    let mut reader = Reader { bytes: contents };
+
+    if reader.take(4)? != MAGIC {
+        return Err(invalid("not a world file"));
+    }
+    let version = reader.u16()?;
+    if version != VERSION {
+        return Err(invalid(format!("unsupported world file version {version}")));
+    }
+    let (width, height, chunk_width) = (reader.u16()?, reader.u16()?, reader.u16()?);
+    let dims =
+        Dimensions::new(width, height, chunk_width).map_err(|err| invalid(err.to_string()))?;
+
+    let mut world = World::with_dimensions(dims, reader.u64()?);
+    world.ticks = reader.u64()?;
+
+    for _ in 0..reader.u32()? {
+        let (tick, pos, reason) = (reader.u64()?, reader.u32()? as usize, reader.take(1)?[0]);
+        let reason = Reason::try_from(reason)
+            .map_err(|reason| invalid(format!("unknown scheduled tick reason {reason}")))?;
+        if pos >= dims.volume() {
+            return Err(invalid("world file schedules a tick outside the world"));
+        }
+        world.schedule.add(ScheduledTick { tick, pos, reason });
+    }
+
+    for chunk in 0..dims.chunks() {
+        world.weather.clouds[chunk] = reader.u32()?;
+    }
+
+    for chunk in 0..dims.chunks() {
+        let len = reader.u32()? as usize;
+        decode_chunk(reader.take(len)?, world.blocks.chunk_mut(chunk))?;
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(invalid("world file has trailing data"));
+    }
+    if fnv1a(contents).to_le_bytes() != checksum {
+        return Err(invalid(
+            "world file checksum doesn't match, it may be corrupted",
+        ));
+    }
+
+    world.wake_all();
+    world.heights.write_all(dims.height());
+    world.heights.settle(&world.blocks, dims.height());
+    world.temperature.touch_all();
+    Ok(world)
+}
+
+/// takes every byte as is. The states world keeps counters in its blocks
+/// rather than types and rotations, and blocks only ever get invalid data
+/// through [`World::process_update`], which rejects it
+fn decode_chunk(body: &[u8], blocks: &mut [Block]) -> io::Result<()> {
+    let mut reader = Reader { bytes: body };
+    let mut i = 0;
+    while !reader.bytes.is_empty() {
+        let run = reader.u16()? as usize + 1;
+        let data = reader.take(1)?[0];
+        let run = blocks
+            .get_mut(i..i + run)
+            .ok_or_else(|| invalid("world file chunk has too many blocks"))?;
+        run.fill(Block::new(data));
+        i += run.len();
+    }
+
+    if i != blocks.len() {
+        return Err(invalid("world file chunk is missing blocks"));
+    }
+    Ok(())
+}
+
+/// reads little endian numbers off the front of a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            return Err(invalid("world file is truncated"));
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+/// 64 bit FNV-1a hash
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::world::{
+        block::{BlockUpdate, TYPE_STONE},
+        position::{self, Dimensions},
+        GenerationConfig, Reason, World,
+    };
+
+    use super::{decode, encode, fnv1a};
+
+    #[test]
+    fn test_round_trip() {
+        let mut world = World::generate(&GenerationConfig {
+            seed: 5,
+            ..Default::default()
+        })
+        .unwrap();
+        world.end_tick();
+        world.weather.gather(3);
+        world.weather.gather(3);
+
+        let path = env::temp_dir().join("ill_of_the_world_round_trip.world");
+        world.save(&path).unwrap();
+        let loaded = World::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.seed(), 5);
+        assert_eq!(loaded.ticks(), 1);
+        assert!(world.blocks == loaded.blocks);
+        assert_eq!(loaded.weather.clouds, world.weather.clouds);
+    }
+
+    #[test]
+    fn test_schedule_round_trip() {
+        let mut world = World::empty();
+        let dims = world.dimensions();
+        world.schedule(position::from_xyz(dims, 9, 9, 9), 50, Reason::Sprout);
+        world.schedule(position::from_xyz(dims, 1, 2, 3), 10, Reason::Sprout);
+        world.schedule(position::from_xyz(dims, 3, 2, 1), 10, Reason::Sprout);
+        world.schedule(position::from_xyz(dims, 3, 2, 1), 20, Reason::Spread);
+        world.schedule(position::from_xyz(dims, 4, 4, 4), 20, Reason::BurnOut);
+        world.schedule(position::from_xyz(dims, 4, 4, 4), 30, Reason::Harden);
+
+        let loaded = decode(&encode(&world)).unwrap();
+        assert!(world.scheduled().eq(loaded.scheduled()));
+        assert_eq!(loaded.scheduled().count(), 6);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut bytes = encode(&World::empty());
+        bytes.truncate(bytes.len() - 8);
+        bytes[4..6].copy_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&fnv1a(&bytes).to_le_bytes());
+
+        let err = decode(&bytes).err().unwrap();
+        assert!(err.to_string().contains("version 2"), "{err}");
+    }
+
+    #[test]
+    fn test_small_world_round_trip() {
+        let dims = Dimensions::new(32, 64, 8).unwrap();
+        let mut world = World::with_dimensions(dims, 2);
+        world
+            .get_block_mut(position::from_xyz(dims, 31, 63, 31))
+            .data = TYPE_STONE;
+
+        let loaded = decode(&encode(&world)).unwrap();
+        assert_eq!(loaded.dimensions(), dims);
+        assert!(world.blocks == loaded.blocks);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let bytes = encode(&World::empty());
+
+        for len in [0, 10, 40, bytes.len() - 9] {
+            let err = decode(&bytes[..len]).err().unwrap();
+            assert!(err.to_string().contains("truncated"), "{len}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let mut world = World::empty();
+        world
+            .get_block_mut(position::from_xyz(world.dimensions(), 3, 3, 3))
+            .data = TYPE_STONE;
+        let mut bytes = encode(&world);
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(decode(&bytes).is_err());
+        bytes[last] ^= 1;
+
+        // the data byte of the very last run in the file
+        let data = bytes.len() - 9;
+        bytes[data] ^= 1;
+        let err = decode(&bytes).err().unwrap();
+        assert!(err.to_string().contains("checksum"), "{err}");
+        bytes[data] ^= 1;
+
+        bytes[0] = b'X';
+        assert!(decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_states_round_trip() {
+        // stone's wear counter, which isn't a valid type and rotation
+        let mut states = World::empty();
+        let pos = position::from_xyz(states.dimensions(), 3, 3, 3);
+        states.get_block_mut(pos).data = 115;
+
+        let loaded = decode(&encode(&states)).unwrap();
+        assert_eq!(loaded.get_block(pos).data, 115);
+    }
+
+    #[test]
+    fn test_rejected_update_round_trip() {
+        let mut world = World::empty();
+        let pos = position::from_xyz(world.dimensions(), 3, 3, 3);
+        let update = BlockUpdate::new(world.dimensions(), pos, 0xf0);
+        assert!(!world.process_update(update));
+        assert_eq!(world.get_block(pos).data, 0);
+
+        let loaded = decode(&encode(&world)).unwrap();
+        assert!(world.blocks == loaded.blocks);
+    }
+}
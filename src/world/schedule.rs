@@ -0,0 +1,183 @@
+//! block ticks scheduled for later
+//!
+//! a block that needs to act again in a while, but has nothing to do until
+//! then, schedules a tick rather than keeping its column awake. Scheduled ticks
+//! are handled in order of when they're due, then position, then reason, so
+//! the order never depends on when or where they were scheduled from
+//!
+//! a block that changes is a different block, so it forgets any ticks it was
+//! waiting on
+
+use std::collections::{BTreeSet, HashMap};
+
+use super::position::Position;
+
+/// why a block asked to be ticked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Reason {
+    /// a seed resting on mud sprouts
+    Sprout = 0,
+    /// the ill spreads in its next wave
+    Spread = 1,
+    /// fire burns out
+    BurnOut = 2,
+    /// lava in the open hardens into stone
+    Harden = 3,
+}
+
+impl Reason {
+    pub const ALL: [Reason; 4] = [
+        Reason::Sprout,
+        Reason::Spread,
+        Reason::BurnOut,
+        Reason::Harden,
+    ];
+}
+
+impl TryFrom<u8> for Reason {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Reason::Sprout),
+            1 => Ok(Reason::Spread),
+            2 => Ok(Reason::BurnOut),
+            3 => Ok(Reason::Harden),
+            _ => Err(value),
+        }
+    }
+}
+
+/// a block to tick once the world reaches `tick`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScheduledTick {
+    // fields are in the order scheduled ticks are handled in
+    pub tick: u64,
+    pub pos: Position,
+    pub reason: Reason,
+}
+
+#[derive(Default)]
+pub struct Schedule {
+    queue: BTreeSet<ScheduledTick>,
+    /// when every position waiting on a tick is due and why, so nothing gets scheduled twice
+    pending: HashMap<(Position, Reason), u64>,
+}
+
+impl Schedule {
+    /// schedules the tick, unless the block is already waiting on one for the same reason.
+    /// Returns whether it was scheduled
+    pub fn add(&mut self, scheduled: ScheduledTick) -> bool {
+        let key = (scheduled.pos, scheduled.reason);
+        if self.pending.contains_key(&key) {
+            return false;
+        }
+        self.pending.insert(key, scheduled.tick);
+        self.queue.insert(scheduled);
+        true
+    }
+
+    /// removes and returns every tick due by `now` at a position `wanted` accepts, in order
+    pub fn take_due(
+        &mut self,
+        now: u64,
+        mut wanted: impl FnMut(Position) -> bool,
+    ) -> Vec<ScheduledTick> {
+        let due = self
+            .queue
+            .iter()
+            .take_while(|scheduled| scheduled.tick <= now)
+            .filter(|scheduled| wanted(scheduled.pos))
+            .copied()
+            .collect::<Vec<_>>();
+
+        for scheduled in &due {
+            self.queue.remove(scheduled);
+            self.pending.remove(&(scheduled.pos, scheduled.reason));
+        }
+        due
+    }
+
+    /// cancels the block's tick for `reason`, if it's waiting on one. Returns whether it was
+    pub fn remove(&mut self, pos: Position, reason: Reason) -> bool {
+        match self.pending.remove(&(pos, reason)) {
+            Some(tick) => self.queue.remove(&ScheduledTick { tick, pos, reason }),
+            None => false,
+        }
+    }
+
+    /// cancels every tick the block is waiting on
+    pub fn clear(&mut self, pos: Position) {
+        if !self.pending.is_empty() {
+            for reason in Reason::ALL {
+                self.remove(pos, reason);
+            }
+        }
+    }
+
+    /// every scheduled tick, in the order they'll be handled
+    pub fn iter(&self) -> impl Iterator<Item = &ScheduledTick> {
+        self.queue.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reason, Schedule, ScheduledTick};
+
+    fn at(tick: u64, pos: usize, reason: Reason) -> ScheduledTick {
+        ScheduledTick { tick, pos, reason }
+    }
+
+    #[test]
+    fn test_due_in_order() {
+        let mut schedule = Schedule::default();
+        assert!(schedule.add(at(5, 30, Reason::Sprout)));
+        assert!(schedule.add(at(3, 40, Reason::Sprout)));
+        assert!(schedule.add(at(5, 10, Reason::Sprout)));
+        assert!(schedule.add(at(5, 20, Reason::Sprout)));
+        assert!(schedule.add(at(9, 50, Reason::Sprout)));
+        // already waiting to sprout
+        assert!(!schedule.add(at(4, 30, Reason::Sprout)));
+
+        assert!(schedule.take_due(2, |_| true).is_empty());
+        assert_eq!(
+            schedule.take_due(5, |pos| pos != 30),
+            [
+                at(3, 40, Reason::Sprout),
+                at(5, 10, Reason::Sprout),
+                at(5, 20, Reason::Sprout)
+            ]
+        );
+        assert_eq!(schedule.take_due(5, |_| true), [at(5, 30, Reason::Sprout)]);
+        assert_eq!(schedule.len(), 1);
+
+        // can be scheduled again once it's been handled
+        assert!(schedule.add(at(12, 30, Reason::Sprout)));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut schedule = Schedule::default();
+        assert!(schedule.add(at(5, 30, Reason::BurnOut)));
+        assert!(schedule.add(at(6, 30, Reason::Harden)));
+        assert!(schedule.add(at(5, 40, Reason::BurnOut)));
+
+        assert!(schedule.remove(30, Reason::Harden));
+        assert!(!schedule.remove(30, Reason::Harden));
+        schedule.clear(30);
+        assert_eq!(
+            schedule.take_due(10, |_| true),
+            [at(5, 40, Reason::BurnOut)]
+        );
+
+        // free to be scheduled again, for a new time
+        assert!(schedule.add(at(20, 30, Reason::BurnOut)));
+        assert_eq!(schedule.len(), 1);
+    }
+}
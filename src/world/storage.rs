@@ -0,0 +1,321 @@
+//! how a world keeps its blocks in memory
+//!
+//! by default every block is stored as is, which is 16 MiB for a full size world. Most
+//! chunks only hold a handful of distinct blocks though, so a world can be
+//! packed chunk by chunk into a palette of those blocks plus a few bits per
+//! block pointing into it, or just one block if the whole chunk is the same
+//!
+//! writing to a packed chunk unpacks it, so a packed world should be
+//! compacted again every so often
+
+use std::{borrow::Cow, mem};
+
+use super::{
+    block::Block,
+    position::{Dimensions, Position},
+};
+
+pub struct Storage {
+    /// how many blocks are in each chunk
+    chunk_volume: usize,
+    layout: Layout,
+}
+
+pub enum Layout {
+    /// every block in chunk-column-block order
+    Flat(Vec<Block>),
+    /// every chunk stored on its own
+    Chunks(Vec<Chunk>),
+}
+
+pub enum Chunk {
+    /// every block in the chunk is the same
+    Uniform(Block),
+    Packed(Packed),
+    /// a chunk that has been written to since it was last packed
+    Unpacked(Vec<Block>),
+}
+
+/// a palette of the distinct blocks in a chunk, and each block's index into it
+pub struct Packed {
+    palette: Vec<Block>,
+    /// bits used by each index
+    bits: u32,
+    /// indices are never split across words, so some bits at the top can go unused
+    words: Vec<u64>,
+}
+
+impl Storage {
+    /// a flat world of all zeros
+    pub fn flat(dims: Dimensions) -> Storage {
+        Storage {
+            chunk_volume: dims.chunk_volume(),
+            layout: Layout::Flat(vec![Block::default(); dims.volume()]),
+        }
+    }
+
+    pub fn get(&self, pos: Position) -> &Block {
+        match &self.layout {
+            Layout::Flat(blocks) => &blocks[pos],
+            Layout::Chunks(chunks) => chunks[pos / self.chunk_volume].get(pos % self.chunk_volume),
+        }
+    }
+
+    /// a packed chunk is unpacked to hand out the reference
+    pub fn get_mut(&mut self, pos: Position) -> &mut Block {
+        let volume = self.chunk_volume;
+        match &mut self.layout {
+            Layout::Flat(blocks) => &mut blocks[pos],
+            Layout::Chunks(chunks) => &mut chunks[pos / volume].unpack(volume)[pos % volume],
+        }
+    }
+
+    /// how many chunks there are
+    pub fn chunks(&self) -> usize {
+        match &self.layout {
+            Layout::Flat(blocks) => blocks.len() / self.chunk_volume,
+            Layout::Chunks(chunks) => chunks.len(),
+        }
+    }
+
+    /// every block in the chunk, in column-block order
+    pub fn chunk(&self, chunk: usize) -> Cow<'_, [Block]> {
+        let volume = self.chunk_volume;
+        match &self.layout {
+            Layout::Flat(blocks) => Cow::Borrowed(&blocks[chunk * volume..(chunk + 1) * volume]),
+            Layout::Chunks(chunks) => chunks[chunk].blocks(volume),
+        }
+    }
+
+    /// every block in the chunk, in column-block order, unpacking it if needed
+    pub fn chunk_mut(&mut self, chunk: usize) -> &mut [Block] {
+        let volume = self.chunk_volume;
+        match &mut self.layout {
+            Layout::Flat(blocks) => &mut blocks[chunk * volume..(chunk + 1) * volume],
+            Layout::Chunks(chunks) => chunks[chunk].unpack(volume),
+        }
+    }
+
+    /// packs every chunk that isn't packed, switching a flat world over to chunks
+    pub fn compact(&mut self) {
+        match &mut self.layout {
+            Layout::Flat(blocks) => {
+                let chunks = blocks.chunks(self.chunk_volume).map(Chunk::pack).collect();
+                self.layout = Layout::Chunks(chunks);
+            }
+            Layout::Chunks(chunks) => {
+                for chunk in chunks {
+                    if let Chunk::Unpacked(blocks) = chunk {
+                        *chunk = Chunk::pack(blocks);
+                    }
+                }
+            }
+        }
+    }
+
+    /// roughly how many bytes the blocks take up
+    pub fn memory_usage(&self) -> usize {
+        match &self.layout {
+            Layout::Flat(blocks) => blocks.len() * mem::size_of::<Block>(),
+            Layout::Chunks(chunks) => chunks.iter().map(Chunk::memory_usage).sum(),
+        }
+    }
+}
+
+impl PartialEq for Storage {
+    fn eq(&self, other: &Storage) -> bool {
+        self.chunk_volume == other.chunk_volume
+            && self.chunks() == other.chunks()
+            && (0..self.chunks()).all(|chunk| self.chunk(chunk) == other.chunk(chunk))
+    }
+}
+
+impl Chunk {
+    /// the smallest way to store the given chunk's blocks
+    fn pack(blocks: &[Block]) -> Chunk {
+        // past this many distinct blocks, indices take 8 bits and are no smaller than the blocks
+        const MAX_PALETTE: usize = 128;
+
+        let mut palette = Vec::new();
+        let mut indices = [None; 256];
+        for block in blocks {
+            let index = &mut indices[block.data as usize];
+            if index.is_none() {
+                if palette.len() == MAX_PALETTE {
+                    return Chunk::Unpacked(blocks.to_vec());
+                }
+                *index = Some(palette.len());
+                palette.push(*block);
+            }
+        }
+
+        if palette.len() == 1 {
+            return Chunk::Uniform(palette[0]);
+        }
+        let bits = usize::BITS - (palette.len() - 1).leading_zeros();
+
+        let per_word = 64 / bits as usize;
+        let mut words = vec![0; blocks.len().div_ceil(per_word)];
+        for (i, block) in blocks.iter().enumerate() {
+            let index = indices[block.data as usize].unwrap() as u64;
+            words[i / per_word] |= index << (i % per_word * bits as usize);
+        }
+
+        Chunk::Packed(Packed {
+            palette,
+            bits,
+            words,
+        })
+    }
+
+    fn get(&self, i: usize) -> &Block {
+        match self {
+            Chunk::Uniform(block) => block,
+            Chunk::Packed(packed) => packed.get(i),
+            Chunk::Unpacked(blocks) => &blocks[i],
+        }
+    }
+
+    /// every block in the chunk, given how many there are
+    fn blocks(&self, volume: usize) -> Cow<'_, [Block]> {
+        match self {
+            Chunk::Unpacked(blocks) => Cow::Borrowed(blocks),
+            chunk => Cow::Owned((0..volume).map(|i| *chunk.get(i)).collect()),
+        }
+    }
+
+    fn unpack(&mut self, volume: usize) -> &mut [Block] {
+        if !matches!(self, Chunk::Unpacked(_)) {
+            *self = Chunk::Unpacked(self.blocks(volume).into_owned());
+        }
+        match self {
+            Chunk::Unpacked(blocks) => blocks,
+            _ => unreachable!(),
+        }
+    }
+
+    fn memory_usage(&self) -> usize {
+        let contents = match self {
+            Chunk::Uniform(_) => 0,
+            Chunk::Packed(packed) => {
+                packed.palette.len() * mem::size_of::<Block>() + packed.words.len() * 8
+            }
+            Chunk::Unpacked(blocks) => blocks.len() * mem::size_of::<Block>(),
+        };
+        mem::size_of::<Chunk>() + contents
+    }
+}
+
+impl Packed {
+    fn get(&self, i: usize) -> &Block {
+        let per_word = 64 / self.bits as usize;
+        let word = self.words[i / per_word] >> (i % per_word * self.bits as usize);
+        &self.palette[(word & ((1 << self.bits) - 1)) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use crate::world::{
+        block::{Block, TYPE_AIR, TYPE_DIRT, TYPE_STONE, TYPE_WATER},
+        position, GenerationConfig, World,
+    };
+
+    use super::{Chunk, Layout};
+
+    #[test]
+    fn test_packed_matches_flat() {
+        let flat = World::generate(&GenerationConfig::default()).unwrap();
+        let mut packed = World::generate(&GenerationConfig::default()).unwrap();
+        packed.compact();
+
+        assert!(flat.blocks == packed.blocks);
+        for pos in (0..flat.dimensions().volume()).step_by(97) {
+            assert_eq!(flat.get_block(pos), packed.get_block(pos));
+        }
+        assert!(packed.memory_usage() < flat.memory_usage());
+    }
+
+    #[test]
+    fn test_writes_unpack_until_compacted() {
+        let mut world = World::empty();
+        world.compact();
+        let Layout::Chunks(chunks) = &world.blocks.layout else {
+            panic!("compacted world should be stored in chunks");
+        };
+        assert!(chunks
+            .iter()
+            .all(|chunk| matches!(chunk, Chunk::Uniform(_))));
+
+        let pos = position::from_xyz(world.dimensions(), 3, 4, 5);
+        world.get_block_mut(pos).data = TYPE_WATER;
+        world.get_block_mut(pos + 1).data = TYPE_STONE;
+        world.get_block_mut(pos + 2).data = TYPE_DIRT;
+        assert_eq!(world.get_block(pos).data, TYPE_WATER);
+
+        world.compact();
+        let Layout::Chunks(chunks) = &world.blocks.layout else {
+            panic!("compacted world should be stored in chunks");
+        };
+        assert!(matches!(chunks[0], Chunk::Packed(_)));
+        assert_eq!(world.get_block(pos).data, TYPE_WATER);
+        assert_eq!(world.get_block(pos + 1).data, TYPE_STONE);
+        assert_eq!(world.get_block(pos + 2).data, TYPE_DIRT);
+        assert_eq!(world.get_block(pos + 3).data, TYPE_AIR);
+    }
+
+    #[test]
+    fn test_every_distinct_block() {
+        let blocks = (0..=255)
+            .map(Block::new)
+            .cycle()
+            .take(4096)
+            .collect::<Vec<_>>();
+        let chunk = Chunk::pack(&blocks);
+        assert!(matches!(chunk, Chunk::Unpacked(_)));
+        assert_eq!(chunk.blocks(blocks.len()), blocks.as_slice());
+
+        // just under the limit still packs
+        let blocks = (0..128)
+            .map(Block::new)
+            .cycle()
+            .take(4096)
+            .collect::<Vec<_>>();
+        let chunk = Chunk::pack(&blocks);
+        assert!(matches!(chunk, Chunk::Packed(_)));
+        assert_eq!(chunk.blocks(blocks.len()), blocks.as_slice());
+    }
+
+    /// run with `cargo test --release -- --ignored --nocapture bench_storage`
+    #[test]
+    #[ignore]
+    fn bench_storage() {
+        let flat = World::generate(&GenerationConfig::default()).unwrap();
+        let mut packed = World::generate(&GenerationConfig::default()).unwrap();
+        packed.compact();
+
+        for (name, mut world) in [("flat", flat), ("packed", packed)] {
+            let memory = world.memory_usage();
+            let start = Instant::now();
+            let mut sum = 0u64;
+            for pos in 0..world.dimensions().volume() {
+                sum += world.get_block(pos).data as u64;
+            }
+            let elapsed = start.elapsed();
+
+            let mut states = World::empty();
+            let start = Instant::now();
+            for _ in 0..10 {
+                world.simulate(&mut states);
+            }
+
+            println!(
+                "{name}: {} KiB, reading every block took {elapsed:?} (sum {sum}), 10 ticks took {:?}",
+                memory / 1024,
+                start.elapsed(),
+            );
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! heat
+//!
+//! the world is split into cells a few blocks on a side, each with a single
+//! temperature. A cell with something hot in it, like lava or fire, is kept at
+//! least as hot as that block (see [`BlockProperties`]). Every tick heat spreads
+//! from each cell into the cells beside it, wrapping around in x and z like the
+//! world does, and warm cells slowly lose heat until they're back to the
+//! temperature of the air. Only warm cells and the cells beside them are updated
+//!
+//! blocks change phase with the temperature of their cell: water boils into
+//! steam, and steam that can't rise any further only condenses once it's cool
+//! enough. A cell that crosses the boiling point wakes its columns so the
+//! blocks in it notice
+//!
+//! [`BlockProperties`]: super::block::BlockProperties
+
+use std::collections::BTreeSet;
+
+use super::{
+    position::{self, Dimensions, Position},
+    World,
+};
+
+/// how many blocks each cell is on a side
+pub const CELL: u16 = 4;
+/// the temperature of the air with nothing hot around
+pub const AMBIENT: u8 = 20;
+/// water in a cell this hot boils into steam, and steam below it condenses
+pub const BOILING: u8 = 100;
+/// heat moving between two cells is their difference divided by this. It
+/// must be more than the six sides of a cell for heat to spread smoothly
+const CONDUCTION: i32 = 8;
+
+pub struct Temperature {
+    dims: Dimensions,
+    /// how many cells fit across the world, rounding up
+    across: usize,
+    /// how many cells fit up the world, rounding up
+    layers: usize,
+    /// the temperature of each cell, ordered by layer, then z, then x
+    cells: Vec<u8>,
+    /// the heat of the hottest block in each cell
+    sources: Vec<u8>,
+    /// one flag per cell whose blocks changed since its source was last found
+    stale: Vec<bool>,
+    /// every stale cell, in the order they went stale
+    changed: Vec<usize>,
+    /// every cell warmer than the air or with a source in it
+    warm: BTreeSet<usize>,
+}
+
+impl Temperature {
+    /// everywhere at the temperature of the air
+    pub fn new(dims: Dimensions) -> Temperature {
+        let across = dims.width().div_ceil(CELL) as usize;
+        let layers = dims.height().div_ceil(CELL) as usize;
+        let count = across * across * layers;
+        Temperature {
+            dims,
+            across,
+            layers,
+            cells: vec![AMBIENT; count],
+            sources: vec![0; count],
+            stale: vec![false; count],
+            changed: Vec::new(),
+            warm: BTreeSet::new(),
+        }
+    }
+
+    /// the temperature of the cell the block is in
+    pub fn at(&self, pos: Position) -> u8 {
+        self.cells[self.cell(pos)]
+    }
+
+    /// notes that the block changed, so its cell's source gets found again next update
+    pub fn touch(&mut self, pos: Position) {
+        let cell = self.cell(pos);
+        if !self.stale[cell] {
+            self.stale[cell] = true;
+            self.changed.push(cell);
+        }
+    }
+
+    /// notes that every block changed, e.g. after they were all loaded at once
+    pub fn touch_all(&mut self) {
+        self.stale.fill(true);
+        self.changed = (0..self.cells.len()).collect();
+    }
+
+    fn cell(&self, pos: Position) -> usize {
+        let (x, y, z) = position::to_xyz(self.dims, pos);
+        let cell = |n: i16| n as usize / CELL as usize;
+        (cell(y) * self.across + cell(z)) * self.across + cell(x)
+    }
+
+    /// the cell's x, y and z among the cells
+    fn coords(&self, cell: usize) -> (usize, usize, usize) {
+        let across = self.across;
+        (
+            cell % across,
+            cell / (across * across),
+            cell / across % across,
+        )
+    }
+
+    /// the cells sharing a face with this one. Heat doesn't leave through the
+    /// top or bottom of the world, so cells past them are left out
+    fn neighbors(&self, cell: usize) -> impl Iterator<Item = usize> {
+        let (across, layers) = (self.across, self.layers);
+        let (x, y, z) = self.coords(cell);
+        let at = move |x: usize, y: usize, z: usize| (y * across + z) * across + x;
+        [
+            Some(at((x + 1) % across, y, z)),
+            Some(at((x + across - 1) % across, y, z)),
+            Some(at(x, y, (z + 1) % across)),
+            Some(at(x, y, (z + across - 1) % across)),
+            (y + 1 < layers).then(|| at(x, y + 1, z)),
+            y.checked_sub(1).map(|y| at(x, y, z)),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// the blocks in the cell, clipped to the world
+    fn blocks(&self, cell: usize) -> impl Iterator<Item = Position> {
+        let (x, y, z) = self.coords(cell);
+        let dims = self.dims;
+        let corner = |n: usize| (n * CELL as usize) as i16;
+        let (x, y, z) = (corner(x), corner(y), corner(z));
+        let clip = |start: i16, size: u16| (start + CELL as i16).min(size as i16) - 1;
+        position::in_box(
+            dims,
+            (x, y, z),
+            (
+                clip(x, dims.width()),
+                clip(y, dims.height()),
+                clip(z, dims.width()),
+            ),
+        )
+    }
+
+    /// what the cell's temperature will be next tick
+    fn next(&self, cell: usize) -> u8 {
+        let here = self.cells[cell] as i32;
+        let flow = self
+            .neighbors(cell)
+            .map(|side| self.cells[side] as i32 - here)
+            .sum::<i32>();
+        let mut next = here + flow / CONDUCTION;
+        if next > AMBIENT as i32 {
+            next -= 1;
+        }
+        next.clamp(self.sources[cell].max(AMBIENT) as i32, u8::MAX as i32) as u8
+    }
+}
+
+/// finds the sources of any cells that changed, then spreads heat for a tick
+pub fn update(world: &mut World) {
+    let temperature = &mut world.temperature;
+    for cell in std::mem::take(&mut temperature.changed) {
+        temperature.stale[cell] = false;
+        let source = temperature
+            .blocks(cell)
+            .map(|pos| world.blocks.get(pos).kind().properties().heat)
+            .max()
+            .unwrap_or(0);
+        temperature.sources[cell] = source;
+        if source > 0 {
+            temperature.warm.insert(cell);
+        }
+    }
+
+    // every cell that could change is warm or beside one that is
+    let mut near = temperature.warm.clone();
+    for &cell in &temperature.warm {
+        near.extend(temperature.neighbors(cell));
+    }
+    let next = near
+        .into_iter()
+        .map(|cell| (cell, temperature.next(cell)))
+        .collect::<Vec<_>>();
+
+    let mut crossed = Vec::new();
+    for (cell, next) in next {
+        let before = temperature.cells[cell];
+        temperature.cells[cell] = next;
+        if next > AMBIENT || temperature.sources[cell] > 0 {
+            temperature.warm.insert(cell);
+        } else {
+            temperature.warm.remove(&cell);
+        }
+        if (before >= BOILING) != (next >= BOILING) {
+            crossed.push(cell);
+        }
+    }
+
+    let height = world.dims.height() as usize;
+    for cell in crossed {
+        let columns = world
+            .temperature
+            .blocks(cell)
+            .map(|pos| pos / height)
+            .collect::<BTreeSet<_>>();
+        for column in columns {
+            world.active[column] = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{TYPE_LAVA, TYPE_STEAM, TYPE_STONE, TYPE_WATER},
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::{AMBIENT, BOILING};
+
+    #[test]
+    fn test_lava_boils_water() {
+        let dims = Dimensions::new(32, 16, 8).unwrap();
+        let mut world = World::with_dimensions(dims, 3);
+        let mut states = World::with_dimensions(dims, 0);
+        world.set_random_ticks(0);
+        // a lava pool sealed under stone, with a cup of water over its middle
+        for pos in position::in_box(dims, (0, 0, 0), (13, 1, 13)) {
+            world.get_block_mut(pos).data = TYPE_STONE;
+        }
+        for pos in position::in_box(dims, (1, 0, 1), (12, 0, 12)) {
+            world.get_block_mut(pos).data = TYPE_LAVA;
+        }
+        for pos in position::in_box(dims, (4, 4, 4), (6, 5, 6)) {
+            world.get_block_mut(pos).data = TYPE_STONE;
+        }
+        let water = position::from_xyz(dims, 5, 5, 5);
+        world.get_block_mut(water).data = TYPE_WATER;
+        world.simulate(&mut states);
+        let far = position::from_xyz(dims, 20, 5, 20);
+        assert_eq!(world.temperature(far), AMBIENT);
+        assert!(world.temperature(position::from_xyz(dims, 1, 0, 1)) > BOILING);
+        assert!(world.temperature(water) < BOILING);
+
+        while world.get_block(water).data == TYPE_WATER {
+            assert!(world.ticks() < 200);
+            world.simulate(&mut states);
+        }
+        assert!(world.temperature(water) >= BOILING);
+        assert_eq!(world.get_block(water).data, TYPE_STEAM);
+        assert_eq!(world.temperature(far), AMBIENT);
+    }
+
+    #[test]
+    fn test_cools_once_the_lava_is_gone() {
+        let dims = Dimensions::new(32, 16, 8).unwrap();
+        let mut world = World::with_dimensions(dims, 3);
+        let mut states = World::with_dimensions(dims, 0);
+        world.set_random_ticks(0);
+        let lava = position::from_xyz(dims, 13, 0, 13);
+        world.get_block_mut(lava).data = TYPE_LAVA;
+        for _ in 0..10 {
+            world.simulate(&mut states);
+        }
+        let beside = position::from_xyz(dims, 17, 0, 13);
+        assert!(world.temperature(beside) > AMBIENT);
+
+        // the lava sits in open air, so it hardens into stone before long
+        while world.get_block(lava).data == TYPE_LAVA {
+            world.simulate(&mut states);
+        }
+        while world.temperature(lava) > AMBIENT {
+            assert!(world.ticks() < 1000);
+            world.simulate(&mut states);
+        }
+        assert_eq!(world.temperature(beside), AMBIENT);
+        assert!(world.temperature.warm.is_empty());
+    }
+}
@@ -1,3 +1,121 @@
+use super::{block::BlockUpdate, position::Dimensions, WeatherState};
+
+/// tag byte starting a block update on the wire
+const TAG_BLOCK: u8 = 0;
+/// tag byte starting the world's dimensions on the wire
+const TAG_DIMENSIONS: u8 = 4;
+/// tag byte starting the weather on the wire
+const TAG_WEATHER: u8 = 5;
+
 pub enum WorldUpdate {
-    
-}
\ No newline at end of file
+    Block(BlockUpdate),
+    /// the size of the world, sent before anything else so the
+    /// other side knows how to read block updates
+    Dimensions(Dimensions),
+    /// the weather across the whole world, sent whenever it changes
+    Weather(WeatherState),
+}
+
+impl WorldUpdate {
+    /// the bytes sent over the network: a tag byte, then the update with every
+    /// number little endian
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            WorldUpdate::Block(update) => vec![
+                TAG_BLOCK,
+                update.chunk,
+                update.column,
+                update.block,
+                update.new_data,
+            ],
+            WorldUpdate::Dimensions(dims) => {
+                let mut bytes = vec![TAG_DIMENSIONS];
+                bytes.extend_from_slice(&dims.width().to_le_bytes());
+                bytes.extend_from_slice(&dims.height().to_le_bytes());
+                bytes.extend_from_slice(&dims.chunk_width().to_le_bytes());
+                bytes
+            }
+            WorldUpdate::Weather(weather) => vec![TAG_WEATHER, weather.cloud_cover, weather.rain],
+        }
+    }
+
+    /// reads an update written by [`WorldUpdate::to_bytes`], or `None`
+    /// if the bytes aren't a valid update
+    pub fn from_bytes(bytes: &[u8]) -> Option<WorldUpdate> {
+        let u16_at = |i: usize| Some(u16::from_le_bytes(bytes.get(i..i + 2)?.try_into().ok()?));
+
+        match *bytes.first()? {
+            TAG_BLOCK => match bytes[1..] {
+                [chunk, column, block, new_data] => Some(WorldUpdate::Block(BlockUpdate {
+                    chunk,
+                    column,
+                    block,
+                    new_data,
+                })),
+                _ => None,
+            },
+            TAG_DIMENSIONS if bytes.len() == 7 => {
+                Dimensions::new(u16_at(1)?, u16_at(3)?, u16_at(5)?)
+                    .ok()
+                    .map(WorldUpdate::Dimensions)
+            }
+            TAG_WEATHER => match bytes[1..] {
+                [cloud_cover, rain] => {
+                    Some(WorldUpdate::Weather(WeatherState { cloud_cover, rain }))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{block::BlockUpdate, position::Dimensions, WeatherState};
+
+    use super::WorldUpdate;
+
+    #[test]
+    fn test_wire_round_trip() {
+        let dims = Dimensions::new(64, 64, 16).unwrap();
+        let bytes = WorldUpdate::Dimensions(dims).to_bytes();
+        assert!(matches!(
+            WorldUpdate::from_bytes(&bytes),
+            Some(WorldUpdate::Dimensions(read)) if read == dims
+        ));
+
+        let update = BlockUpdate {
+            chunk: 15,
+            column: 3,
+            block: 63,
+            new_data: 9,
+        };
+        let bytes = WorldUpdate::Block(update).to_bytes();
+        assert!(matches!(
+            WorldUpdate::from_bytes(&bytes),
+            Some(WorldUpdate::Block(BlockUpdate {
+                chunk: 15,
+                column: 3,
+                block: 63,
+                new_data: 9
+            }))
+        ));
+
+        let weather = WeatherState {
+            cloud_cover: 40,
+            rain: 15,
+        };
+        let bytes = WorldUpdate::Weather(weather).to_bytes();
+        assert!(matches!(
+            WorldUpdate::from_bytes(&bytes),
+            Some(WorldUpdate::Weather(read)) if read == weather
+        ));
+
+        // 512 wide is too big
+        let mut bytes = WorldUpdate::Dimensions(dims).to_bytes();
+        bytes[1..3].copy_from_slice(&512u16.to_le_bytes());
+        assert!(WorldUpdate::from_bytes(&bytes).is_none());
+        assert!(WorldUpdate::from_bytes(&[0, 1, 2]).is_none());
+    }
+}
@@ -0,0 +1,216 @@
+//! clouds and rain
+//!
+//! steam that rises as far as the cloud layer gathers into the cloud above its
+//! chunk, each cloud holding however many blocks of water went into it. Clouds
+//! drift east with the wind, and any holding enough water rain it back down a
+//! block at a time onto the surface below. Rain that would fall into water
+//! stays in the cloud, so no water is made or lost along the way. Clouds are
+//! saved with the world
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{
+    block::{BlockKind, BlockUpdate},
+    position::{self, Dimensions, Position},
+    World,
+};
+
+/// how many ticks go by between clouds drifting one chunk east
+pub const DRIFT_TICKS: u64 = 40;
+/// how much water a cloud has to hold before it rains
+pub const RAIN_THRESHOLD: u32 = 4;
+/// the chance each tick that a raining cloud drops a block of water
+const RAIN_CHANCE: f64 = 0.25;
+
+/// the weather across the whole world, for showing to players
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WeatherState {
+    /// how much of the sky has cloud in it, from 0 to 100
+    pub cloud_cover: u8,
+    /// how much of the sky is raining, from 0 to 100
+    pub rain: u8,
+}
+
+pub struct Weather {
+    /// how much water the cloud above each chunk holds, in chunk order
+    pub(super) clouds: Vec<u32>,
+}
+
+impl Weather {
+    /// clear skies
+    pub fn new(dims: Dimensions) -> Weather {
+        Weather {
+            clouds: vec![0; dims.chunks()],
+        }
+    }
+
+    /// adds a block of water to the cloud above the chunk. A cloud can't hold
+    /// more than all the blocks in the world, so this never overflows
+    pub fn gather(&mut self, chunk: u8) {
+        self.clouds[chunk as usize] += 1;
+    }
+
+    pub fn state(&self) -> WeatherState {
+        let percent = |count: usize| (count * 100 / self.clouds.len()) as u8;
+        WeatherState {
+            cloud_cover: percent(self.clouds.iter().filter(|&&cloud| cloud > 0).count()),
+            rain: percent(
+                self.clouds
+                    .iter()
+                    .filter(|&&cloud| cloud >= RAIN_THRESHOLD)
+                    .count(),
+            ),
+        }
+    }
+
+    /// moves every cloud one chunk east, wrapping around the world
+    fn drift(&mut self, dims: Dimensions) {
+        let across = dims.chunks_across() as usize;
+        for row in self.clouds.chunks_mut(across) {
+            row.rotate_right(1);
+        }
+    }
+}
+
+/// the lowest height steam gathers into clouds at
+pub fn cloud_layer(dims: Dimensions) -> u16 {
+    dims.height() / 4 * 3
+}
+
+/// drifts the clouds and lets them rain, returning the blocks of rain that landed
+pub fn update(world: &mut World, states: &mut World) -> Vec<BlockUpdate> {
+    let dims = world.dims;
+    if world.ticks > 0 && world.ticks.is_multiple_of(DRIFT_TICKS) {
+        world.weather.drift(dims);
+    }
+
+    // the weather gets its own streams, apart from every chunk's
+    let mut rng = ChaCha8Rng::seed_from_u64(world.seed);
+    rng.set_stream(world.ticks | 1 << 62);
+
+    let mut updates = Vec::new();
+    for chunk in 0..dims.chunks() {
+        if world.weather.clouds[chunk] < RAIN_THRESHOLD || !rng.gen_bool(RAIN_CHANCE) {
+            continue;
+        }
+        let column = rng.gen_range(0..dims.chunk_columns()) as u8;
+        if let Some(pos) = landing(world, position::from_ccb(dims, chunk as u8, column, 0)) {
+            world.weather.clouds[chunk] -= 1;
+            world.get_block_mut(pos).data = BlockKind::Water as u8;
+            states.get_block_mut(pos).data = 0;
+            updates.push(BlockUpdate::new(dims, pos, BlockKind::Water as u8));
+        }
+    }
+    updates
+}
+
+/// the air just above the surface where rain falling down the column whose
+/// bottom block is `bottom` lands. `None` if the surface is water or reaches
+/// up into the clouds
+fn landing(world: &World, bottom: Position) -> Option<Position> {
+    let height = world.dims.height();
+    let top = world
+        .heights
+        .top(&world.blocks, height, bottom / height as usize);
+    if top >= cloud_layer(world.dims) {
+        return None;
+    }
+
+    let above = bottom + top as usize;
+    let surface = above.checked_sub(1).filter(|_| top > 0);
+    match surface {
+        Some(surface) if world.get_block(surface).kind() == BlockKind::Water => None,
+        _ => Some(above),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        block::{TYPE_STEAM, TYPE_STONE, TYPE_WATER},
+        position::{self, Dimensions},
+        World,
+    };
+
+    use super::{cloud_layer, DRIFT_TICKS, RAIN_THRESHOLD};
+
+    fn floored(dims: Dimensions) -> World {
+        let mut world = World::with_dimensions(dims, 8);
+        world.set_random_ticks(0);
+        for pos in position::in_box(dims, (0, 0, 0), (31, 0, 31)) {
+            world.get_block_mut(pos).data = TYPE_STONE;
+        }
+        world
+    }
+
+    #[test]
+    fn test_steam_rains_back_down() {
+        let dims = Dimensions::new(32, 32, 8).unwrap();
+        let mut world = floored(dims);
+        let mut states = World::with_dimensions(dims, 0);
+        for x in 0..8 {
+            world
+                .get_block_mut(position::from_xyz(dims, 2 + x % 2, 3, 2 + x / 2))
+                .data = TYPE_STEAM;
+        }
+
+        // the steam is blocked by nothing, so all of it rises into chunk 0's cloud
+        for _ in 0..cloud_layer(dims) {
+            world.simulate(&mut states);
+        }
+        let held = world.weather.clouds[0] + world.weather.clouds[1];
+        assert_eq!(held, 8);
+        assert!(world.weather().rain > 0);
+
+        while world.ticks() < DRIFT_TICKS * 2 {
+            world.simulate(&mut states);
+        }
+        // every drop of rain is a source that stays put, and the rest is still up
+        let rained = position::in_box(dims, (0, 1, 0), (31, 1, 31))
+            .filter(|&pos| {
+                world.get_block(pos).data == TYPE_WATER && states.get_block(pos).data == 0
+            })
+            .count() as u32;
+        let held = world.weather.clouds.iter().sum::<u32>();
+        assert!(rained > 0);
+        assert_eq!(rained + held, 8);
+        // clouds stop raining once they run low
+        assert!(held >= RAIN_THRESHOLD - 1);
+    }
+
+    #[test]
+    fn test_clouds_drift_east() {
+        let dims = Dimensions::new(32, 32, 8).unwrap();
+        let mut world = floored(dims);
+        let mut states = World::with_dimensions(dims, 0);
+        // across the seam from the west edge
+        world.weather.gather(3);
+        world.weather.gather(7);
+
+        while world.ticks() <= DRIFT_TICKS {
+            world.simulate(&mut states);
+        }
+        assert_eq!(world.weather.clouds[0], 1);
+        assert_eq!(world.weather.clouds[4], 1);
+        assert_eq!(world.weather().cloud_cover, 12);
+    }
+
+    #[test]
+    fn test_rain_stays_up_over_water() {
+        let dims = Dimensions::new(32, 32, 8).unwrap();
+        let mut world = floored(dims);
+        let mut states = World::with_dimensions(dims, 0);
+        for pos in position::in_box(dims, (0, 1, 0), (31, 1, 31)) {
+            world.get_block_mut(pos).data = TYPE_WATER;
+        }
+        for _ in 0..10 {
+            world.weather.gather(0);
+        }
+
+        while world.ticks() < DRIFT_TICKS {
+            assert!(world.simulate(&mut states).updates.is_empty());
+        }
+        assert_eq!(world.weather.clouds[0], 10);
+    }
+}